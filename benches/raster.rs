@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use wifiqr::code::{make_image, make_image_with, RenderOptions};
+use wifiqr::{ErrorCorrection, QrMatrix};
+
+/// a version 10 code, about the size of a WPA2 network with a long passphrase
+fn large_code() -> QrMatrix {
+    QrMatrix::encode_text(
+        "WIFI:T:WPA2;S:benchmark network;P:a rather long passphrase, with 63 characters in it .....;;",
+        ErrorCorrection::High,
+    )
+    .unwrap()
+}
+
+/// make_image at increasing scales: the work grows with the number of pixels, not scale^4
+fn scales(c: &mut Criterion) {
+    let qr = large_code();
+    let mut group = c.benchmark_group("make_image");
+
+    for scale in [4, 10, 20, 40].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(scale), scale, |b, &scale| {
+            b.iter(|| make_image(black_box(&qr), scale, scale * 2))
+        });
+    }
+
+    group.finish();
+}
+
+/// a batch job: one printable image for each of 50 networks
+fn batch(c: &mut Criterion) {
+    let codes: Vec<QrMatrix> = (0..50)
+        .map(|i| {
+            QrMatrix::encode_text(
+                &format!("WIFI:T:WPA2;S:room-{};P:guest password {};;", i, i),
+                ErrorCorrection::High,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let options = RenderOptions {
+        size: "40mm".parse().ok(),
+        dpi: Some(600),
+        ..RenderOptions::default()
+    };
+
+    c.bench_function("batch of 50 at 40mm, 600 DPI", |b| {
+        b.iter(|| {
+            for qr in codes.iter() {
+                black_box(make_image_with(qr, &options).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, scales, batch);
+criterion_main!(benches);
@@ -0,0 +1,202 @@
+/// Packed 1-bit bitmaps for microcontrollers and e-paper displays
+///
+/// The code is rasterized like image output, then packed eight pixels to a byte and written as a C
+/// header or an XBM file that firmware can compile in. A set bit is a dark pixel, unless inverted
+/// for controllers where 1 is white.
+pub mod methods {
+    use std::io::{self, Write};
+    use std::str::FromStr;
+
+    use crate::matrix::methods::QrMatrix;
+    use crate::render::methods::{pack_bits, RasterLayout};
+
+    /// the number of bytes on each line of a C array
+    const BYTES_PER_LINE: usize = 12;
+
+    /// the order in which pixels are packed into bytes
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum BitmapLayout {
+        /// row by row from the top, the leftmost pixel in the most significant bit. Each row starts
+        /// on a new byte. This is what Adafruit GFX drawBitmap(), GxEPD2 and most e-paper
+        /// controllers (SSD1680, UC8151, IL3820 in landscape) expect
+        Rows,
+        /// column by column from the left, the topmost pixel in the most significant bit. Each
+        /// column starts on a new byte. For panels whose RAM is addressed in portrait while the
+        /// image is drawn rotated, such as many 2.13" and 2.9" modules
+        Columns,
+    }
+
+    impl FromStr for BitmapLayout {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "rows" | "row-major" => Ok(BitmapLayout::Rows),
+                "columns" | "column-major" => Ok(BitmapLayout::Columns),
+                _ => Err(format!(
+                    "Unknown bitmap layout: {}. Try one of: rows, columns",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// how a bitmap is packed and named
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct BitmapOptions {
+        /// the order of pixels in C header output. XBM is always written in rows, least
+        /// significant bit first, as the format requires
+        pub layout: BitmapLayout,
+        /// set the bits of light pixels instead of dark ones
+        pub invert: bool,
+        /// the C identifier of the array. Defines are named after it in uppercase
+        pub name: String,
+    }
+
+    impl Default for BitmapOptions {
+        fn default() -> Self {
+            BitmapOptions {
+                layout: BitmapLayout::Rows,
+                invert: false,
+                name: "wifi_qr".to_string(),
+            }
+        }
+    }
+
+    /// turns a name into a valid C identifier, e.g. `guest-wifi` into `guest_wifi`
+    fn c_identifier(name: &str) -> String {
+        let mut identifier: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+            identifier.insert(0, '_');
+        }
+        identifier
+    }
+
+    /// packs a rasterized QR code into bytes, in the layout from `options`
+    ///
+    /// * qr: an encoded QR code
+    ///
+    /// * raster: the image size, module size and quiet zone, e.g. from RenderOptions::raster_layout()
+    ///
+    /// * options: see BitmapOptions
+    pub fn pack_bitmap(qr: &QrMatrix, raster: &RasterLayout, options: &BitmapOptions) -> Vec<u8> {
+        let (dark, light) = if options.invert { (0, 1) } else { (1, 0) };
+        let pixels = raster.fill(qr, &[dark], &[light]);
+
+        match options.layout {
+            BitmapLayout::Rows => pack_bits(raster.size, &pixels),
+            BitmapLayout::Columns => {
+                let size = raster.size as usize;
+                let transposed: Vec<u8> = (0..size)
+                    .flat_map(|x| pixels.iter().skip(x).step_by(size).copied())
+                    .collect();
+                pack_bits(raster.size, &transposed)
+            }
+        }
+    }
+
+    /// writes bytes as the body of a C array initializer, BYTES_PER_LINE to a line
+    fn write_bytes(bytes: &[u8], out: &mut dyn Write) -> io::Result<()> {
+        let lines: Vec<String> = bytes
+            .chunks(BYTES_PER_LINE)
+            .map(|line| {
+                let hex: Vec<String> = line.iter().map(|b| format!("0x{:02x}", b)).collect();
+                format!("    {}", hex.join(", "))
+            })
+            .collect();
+        writeln!(out, "{}", lines.join(",\n"))
+    }
+
+    /// writes a C header with the packed bitmap as `const uint8_t name[]`, and `NAME_WIDTH` and
+    /// `NAME_HEIGHT` defines
+    ///
+    /// * qr: an encoded QR code
+    ///
+    /// * raster: the image size, module size and quiet zone
+    ///
+    /// * options: see BitmapOptions
+    ///
+    /// * out: where to write the header
+    pub fn write_c_header(
+        qr: &QrMatrix,
+        raster: &RasterLayout,
+        options: &BitmapOptions,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let name = c_identifier(&options.name);
+        let define = name.to_uppercase();
+        let bytes = pack_bitmap(qr, raster, options);
+
+        let order = match options.layout {
+            BitmapLayout::Rows => {
+                "row by row from the top, leftmost pixel in the most significant bit"
+            }
+            BitmapLayout::Columns => {
+                "column by column from the left, topmost pixel in the most significant bit"
+            }
+        };
+        let set = if options.invert { "light" } else { "dark" };
+
+        writeln!(
+            out,
+            "/* QR code bitmap, {0}x{0} pixels at {1} pixels per module */",
+            raster.size, raster.module_size
+        )?;
+        writeln!(
+            out,
+            "/* 1 bit per pixel, {}; set bits are {} */",
+            order, set
+        )?;
+        writeln!(out, "#ifndef {}_H", define)?;
+        writeln!(out, "#define {}_H", define)?;
+        writeln!(out)?;
+        writeln!(out, "#include <stdint.h>")?;
+        writeln!(out)?;
+        writeln!(out, "#define {}_WIDTH {}", define, raster.size)?;
+        writeln!(out, "#define {}_HEIGHT {}", define, raster.size)?;
+        writeln!(out)?;
+        writeln!(out, "const uint8_t {}[{}] = {{", name, bytes.len())?;
+        write_bytes(&bytes, out)?;
+        writeln!(out, "}};")?;
+        writeln!(out)?;
+        writeln!(out, "#endif")
+    }
+
+    /// writes an XBM (X BitMap) image, which is C source too: rows from the top, the leftmost
+    /// pixel in the least significant bit
+    ///
+    /// * qr: an encoded QR code
+    ///
+    /// * raster: the image size, module size and quiet zone
+    ///
+    /// * options: see BitmapOptions. The layout is ignored
+    ///
+    /// * out: where to write the image
+    pub fn write_xbm(
+        qr: &QrMatrix,
+        raster: &RasterLayout,
+        options: &BitmapOptions,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let name = c_identifier(&options.name);
+        let rows = BitmapOptions {
+            layout: BitmapLayout::Rows,
+            ..options.clone()
+        };
+        let bytes: Vec<u8> = pack_bitmap(qr, raster, &rows)
+            .iter()
+            .map(|b| b.reverse_bits())
+            .collect();
+
+        writeln!(out, "#define {}_width {}", name, raster.size)?;
+        writeln!(out, "#define {}_height {}", name, raster.size)?;
+        writeln!(out, "static unsigned char {}_bits[] = {{", name)?;
+        write_bytes(&bytes, out)?;
+        writeln!(out, "}};")
+    }
+}
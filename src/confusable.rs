@@ -0,0 +1,497 @@
+/// Invisible characters, look-alike letters and detached accents in SSIDs and passwords
+///
+/// Credentials copied from a web page, a chat or a PDF often carry characters that look like
+/// nothing, or like something else: a zero-width space, a no-break space, an accent stored apart
+/// from its letter (the NFD form macOS uses for file names), or a Cyrillic `а` in place of a Latin
+/// `a`. The code then carries a different name or password than the access point expects, and
+/// phones fail to join. find_suspicious() points these out by code point, and normalize_text()
+/// repairs the ones with an unambiguous fix.
+pub mod methods {
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    /// characters that take no space when displayed. Zero width joiners and non-joiners between
+    /// two non-ASCII characters are left alone, since emoji sequences and some scripts need them
+    const INVISIBLE: &[(char, &str)] = &[
+        ('\u{AD}', "SOFT HYPHEN"),
+        ('\u{180E}', "MONGOLIAN VOWEL SEPARATOR"),
+        ('\u{200B}', "ZERO WIDTH SPACE"),
+        ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+        ('\u{200D}', "ZERO WIDTH JOINER"),
+        ('\u{200E}', "LEFT-TO-RIGHT MARK"),
+        ('\u{200F}', "RIGHT-TO-LEFT MARK"),
+        ('\u{2060}', "WORD JOINER"),
+        ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+    ];
+
+    /// spaces other than U+0020, which look the same but compare differently
+    const SPACES: &[(char, &str)] = &[
+        ('\u{A0}', "NO-BREAK SPACE"),
+        ('\u{1680}', "OGHAM SPACE MARK"),
+        ('\u{2000}', "EN QUAD"),
+        ('\u{2001}', "EM QUAD"),
+        ('\u{2002}', "EN SPACE"),
+        ('\u{2003}', "EM SPACE"),
+        ('\u{2004}', "THREE-PER-EM SPACE"),
+        ('\u{2005}', "FOUR-PER-EM SPACE"),
+        ('\u{2006}', "SIX-PER-EM SPACE"),
+        ('\u{2007}', "FIGURE SPACE"),
+        ('\u{2008}', "PUNCTUATION SPACE"),
+        ('\u{2009}', "THIN SPACE"),
+        ('\u{200A}', "HAIR SPACE"),
+        ('\u{202F}', "NARROW NO-BREAK SPACE"),
+        ('\u{205F}', "MEDIUM MATHEMATICAL SPACE"),
+        ('\u{3000}', "IDEOGRAPHIC SPACE"),
+    ];
+
+    /// Cyrillic and Greek letters that look like a Latin letter
+    const LOOKALIKES: &[(char, char, &str)] = &[
+        ('\u{410}', 'A', "CYRILLIC CAPITAL LETTER A"),
+        ('\u{412}', 'B', "CYRILLIC CAPITAL LETTER VE"),
+        ('\u{415}', 'E', "CYRILLIC CAPITAL LETTER IE"),
+        ('\u{41A}', 'K', "CYRILLIC CAPITAL LETTER KA"),
+        ('\u{41C}', 'M', "CYRILLIC CAPITAL LETTER EM"),
+        ('\u{41D}', 'H', "CYRILLIC CAPITAL LETTER EN"),
+        ('\u{41E}', 'O', "CYRILLIC CAPITAL LETTER O"),
+        ('\u{420}', 'P', "CYRILLIC CAPITAL LETTER ER"),
+        ('\u{421}', 'C', "CYRILLIC CAPITAL LETTER ES"),
+        ('\u{422}', 'T', "CYRILLIC CAPITAL LETTER TE"),
+        ('\u{425}', 'X', "CYRILLIC CAPITAL LETTER HA"),
+        ('\u{405}', 'S', "CYRILLIC CAPITAL LETTER DZE"),
+        (
+            '\u{406}',
+            'I',
+            "CYRILLIC CAPITAL LETTER BYELORUSSIAN-UKRAINIAN I",
+        ),
+        ('\u{408}', 'J', "CYRILLIC CAPITAL LETTER JE"),
+        ('\u{430}', 'a', "CYRILLIC SMALL LETTER A"),
+        ('\u{435}', 'e', "CYRILLIC SMALL LETTER IE"),
+        ('\u{43E}', 'o', "CYRILLIC SMALL LETTER O"),
+        ('\u{440}', 'p', "CYRILLIC SMALL LETTER ER"),
+        ('\u{441}', 'c', "CYRILLIC SMALL LETTER ES"),
+        ('\u{443}', 'y', "CYRILLIC SMALL LETTER U"),
+        ('\u{445}', 'x', "CYRILLIC SMALL LETTER HA"),
+        ('\u{455}', 's', "CYRILLIC SMALL LETTER DZE"),
+        (
+            '\u{456}',
+            'i',
+            "CYRILLIC SMALL LETTER BYELORUSSIAN-UKRAINIAN I",
+        ),
+        ('\u{458}', 'j', "CYRILLIC SMALL LETTER JE"),
+        ('\u{4BB}', 'h', "CYRILLIC SMALL LETTER SHHA"),
+        ('\u{501}', 'd', "CYRILLIC SMALL LETTER KOMI DE"),
+        ('\u{391}', 'A', "GREEK CAPITAL LETTER ALPHA"),
+        ('\u{392}', 'B', "GREEK CAPITAL LETTER BETA"),
+        ('\u{395}', 'E', "GREEK CAPITAL LETTER EPSILON"),
+        ('\u{396}', 'Z', "GREEK CAPITAL LETTER ZETA"),
+        ('\u{397}', 'H', "GREEK CAPITAL LETTER ETA"),
+        ('\u{399}', 'I', "GREEK CAPITAL LETTER IOTA"),
+        ('\u{39A}', 'K', "GREEK CAPITAL LETTER KAPPA"),
+        ('\u{39C}', 'M', "GREEK CAPITAL LETTER MU"),
+        ('\u{39D}', 'N', "GREEK CAPITAL LETTER NU"),
+        ('\u{39F}', 'O', "GREEK CAPITAL LETTER OMICRON"),
+        ('\u{3A1}', 'P', "GREEK CAPITAL LETTER RHO"),
+        ('\u{3A4}', 'T', "GREEK CAPITAL LETTER TAU"),
+        ('\u{3A5}', 'Y', "GREEK CAPITAL LETTER UPSILON"),
+        ('\u{3A7}', 'X', "GREEK CAPITAL LETTER CHI"),
+        ('\u{3BF}', 'o', "GREEK SMALL LETTER OMICRON"),
+        ('\u{3BD}', 'v', "GREEK SMALL LETTER NU"),
+    ];
+
+    /// the combining accents named in warnings
+    const COMBINING: &[(char, &str)] = &[
+        ('\u{300}', "COMBINING GRAVE ACCENT"),
+        ('\u{301}', "COMBINING ACUTE ACCENT"),
+        ('\u{302}', "COMBINING CIRCUMFLEX ACCENT"),
+        ('\u{303}', "COMBINING TILDE"),
+        ('\u{304}', "COMBINING MACRON"),
+        ('\u{306}', "COMBINING BREVE"),
+        ('\u{307}', "COMBINING DOT ABOVE"),
+        ('\u{308}', "COMBINING DIAERESIS"),
+        ('\u{30A}', "COMBINING RING ABOVE"),
+        ('\u{30B}', "COMBINING DOUBLE ACUTE ACCENT"),
+        ('\u{30C}', "COMBINING CARON"),
+        ('\u{327}', "COMBINING CEDILLA"),
+        ('\u{328}', "COMBINING OGONEK"),
+    ];
+
+    /// base letter, combining accent and the precomposed letter (NFC) for Latin-1 and Latin
+    /// Extended-A, which covers the accented letters of most European languages
+    const COMPOSITIONS: &[(char, char, char)] = &[
+        ('A', '\u{300}', 'À'),
+        ('A', '\u{301}', 'Á'),
+        ('A', '\u{302}', 'Â'),
+        ('A', '\u{303}', 'Ã'),
+        ('A', '\u{308}', 'Ä'),
+        ('A', '\u{30A}', 'Å'),
+        ('C', '\u{327}', 'Ç'),
+        ('E', '\u{300}', 'È'),
+        ('E', '\u{301}', 'É'),
+        ('E', '\u{302}', 'Ê'),
+        ('E', '\u{308}', 'Ë'),
+        ('I', '\u{300}', 'Ì'),
+        ('I', '\u{301}', 'Í'),
+        ('I', '\u{302}', 'Î'),
+        ('I', '\u{308}', 'Ï'),
+        ('N', '\u{303}', 'Ñ'),
+        ('O', '\u{300}', 'Ò'),
+        ('O', '\u{301}', 'Ó'),
+        ('O', '\u{302}', 'Ô'),
+        ('O', '\u{303}', 'Õ'),
+        ('O', '\u{308}', 'Ö'),
+        ('U', '\u{300}', 'Ù'),
+        ('U', '\u{301}', 'Ú'),
+        ('U', '\u{302}', 'Û'),
+        ('U', '\u{308}', 'Ü'),
+        ('Y', '\u{301}', 'Ý'),
+        ('a', '\u{300}', 'à'),
+        ('a', '\u{301}', 'á'),
+        ('a', '\u{302}', 'â'),
+        ('a', '\u{303}', 'ã'),
+        ('a', '\u{308}', 'ä'),
+        ('a', '\u{30A}', 'å'),
+        ('c', '\u{327}', 'ç'),
+        ('e', '\u{300}', 'è'),
+        ('e', '\u{301}', 'é'),
+        ('e', '\u{302}', 'ê'),
+        ('e', '\u{308}', 'ë'),
+        ('i', '\u{300}', 'ì'),
+        ('i', '\u{301}', 'í'),
+        ('i', '\u{302}', 'î'),
+        ('i', '\u{308}', 'ï'),
+        ('n', '\u{303}', 'ñ'),
+        ('o', '\u{300}', 'ò'),
+        ('o', '\u{301}', 'ó'),
+        ('o', '\u{302}', 'ô'),
+        ('o', '\u{303}', 'õ'),
+        ('o', '\u{308}', 'ö'),
+        ('u', '\u{300}', 'ù'),
+        ('u', '\u{301}', 'ú'),
+        ('u', '\u{302}', 'û'),
+        ('u', '\u{308}', 'ü'),
+        ('y', '\u{301}', 'ý'),
+        ('y', '\u{308}', 'ÿ'),
+        ('A', '\u{304}', 'Ā'),
+        ('a', '\u{304}', 'ā'),
+        ('A', '\u{306}', 'Ă'),
+        ('a', '\u{306}', 'ă'),
+        ('A', '\u{328}', 'Ą'),
+        ('a', '\u{328}', 'ą'),
+        ('C', '\u{301}', 'Ć'),
+        ('c', '\u{301}', 'ć'),
+        ('C', '\u{302}', 'Ĉ'),
+        ('c', '\u{302}', 'ĉ'),
+        ('C', '\u{307}', 'Ċ'),
+        ('c', '\u{307}', 'ċ'),
+        ('C', '\u{30C}', 'Č'),
+        ('c', '\u{30C}', 'č'),
+        ('D', '\u{30C}', 'Ď'),
+        ('d', '\u{30C}', 'ď'),
+        ('E', '\u{304}', 'Ē'),
+        ('e', '\u{304}', 'ē'),
+        ('E', '\u{306}', 'Ĕ'),
+        ('e', '\u{306}', 'ĕ'),
+        ('E', '\u{307}', 'Ė'),
+        ('e', '\u{307}', 'ė'),
+        ('E', '\u{328}', 'Ę'),
+        ('e', '\u{328}', 'ę'),
+        ('E', '\u{30C}', 'Ě'),
+        ('e', '\u{30C}', 'ě'),
+        ('G', '\u{302}', 'Ĝ'),
+        ('g', '\u{302}', 'ĝ'),
+        ('G', '\u{306}', 'Ğ'),
+        ('g', '\u{306}', 'ğ'),
+        ('G', '\u{307}', 'Ġ'),
+        ('g', '\u{307}', 'ġ'),
+        ('G', '\u{327}', 'Ģ'),
+        ('g', '\u{327}', 'ģ'),
+        ('H', '\u{302}', 'Ĥ'),
+        ('h', '\u{302}', 'ĥ'),
+        ('I', '\u{303}', 'Ĩ'),
+        ('i', '\u{303}', 'ĩ'),
+        ('I', '\u{304}', 'Ī'),
+        ('i', '\u{304}', 'ī'),
+        ('I', '\u{306}', 'Ĭ'),
+        ('i', '\u{306}', 'ĭ'),
+        ('I', '\u{328}', 'Į'),
+        ('i', '\u{328}', 'į'),
+        ('I', '\u{307}', 'İ'),
+        ('J', '\u{302}', 'Ĵ'),
+        ('j', '\u{302}', 'ĵ'),
+        ('K', '\u{327}', 'Ķ'),
+        ('k', '\u{327}', 'ķ'),
+        ('L', '\u{301}', 'Ĺ'),
+        ('l', '\u{301}', 'ĺ'),
+        ('L', '\u{327}', 'Ļ'),
+        ('l', '\u{327}', 'ļ'),
+        ('L', '\u{30C}', 'Ľ'),
+        ('l', '\u{30C}', 'ľ'),
+        ('N', '\u{301}', 'Ń'),
+        ('n', '\u{301}', 'ń'),
+        ('N', '\u{327}', 'Ņ'),
+        ('n', '\u{327}', 'ņ'),
+        ('N', '\u{30C}', 'Ň'),
+        ('n', '\u{30C}', 'ň'),
+        ('O', '\u{304}', 'Ō'),
+        ('o', '\u{304}', 'ō'),
+        ('O', '\u{306}', 'Ŏ'),
+        ('o', '\u{306}', 'ŏ'),
+        ('O', '\u{30B}', 'Ő'),
+        ('o', '\u{30B}', 'ő'),
+        ('R', '\u{301}', 'Ŕ'),
+        ('r', '\u{301}', 'ŕ'),
+        ('R', '\u{327}', 'Ŗ'),
+        ('r', '\u{327}', 'ŗ'),
+        ('R', '\u{30C}', 'Ř'),
+        ('r', '\u{30C}', 'ř'),
+        ('S', '\u{301}', 'Ś'),
+        ('s', '\u{301}', 'ś'),
+        ('S', '\u{302}', 'Ŝ'),
+        ('s', '\u{302}', 'ŝ'),
+        ('S', '\u{327}', 'Ş'),
+        ('s', '\u{327}', 'ş'),
+        ('S', '\u{30C}', 'Š'),
+        ('s', '\u{30C}', 'š'),
+        ('T', '\u{327}', 'Ţ'),
+        ('t', '\u{327}', 'ţ'),
+        ('T', '\u{30C}', 'Ť'),
+        ('t', '\u{30C}', 'ť'),
+        ('U', '\u{303}', 'Ũ'),
+        ('u', '\u{303}', 'ũ'),
+        ('U', '\u{304}', 'Ū'),
+        ('u', '\u{304}', 'ū'),
+        ('U', '\u{306}', 'Ŭ'),
+        ('u', '\u{306}', 'ŭ'),
+        ('U', '\u{30A}', 'Ů'),
+        ('u', '\u{30A}', 'ů'),
+        ('U', '\u{30B}', 'Ű'),
+        ('u', '\u{30B}', 'ű'),
+        ('U', '\u{328}', 'Ų'),
+        ('u', '\u{328}', 'ų'),
+        ('W', '\u{302}', 'Ŵ'),
+        ('w', '\u{302}', 'ŵ'),
+        ('Y', '\u{302}', 'Ŷ'),
+        ('y', '\u{302}', 'ŷ'),
+        ('Y', '\u{308}', 'Ÿ'),
+        ('Z', '\u{301}', 'Ź'),
+        ('z', '\u{301}', 'ź'),
+        ('Z', '\u{307}', 'Ż'),
+        ('z', '\u{307}', 'ż'),
+        ('Z', '\u{30C}', 'Ž'),
+        ('z', '\u{30C}', 'ž'),
+    ];
+
+    /// what is suspicious about a character
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+    pub enum Suspicion {
+        /// a character that isn't displayed, e.g. U+200B ZERO WIDTH SPACE. Normalizing removes it
+        Invisible,
+        /// a space other than U+0020, e.g. U+00A0 NO-BREAK SPACE. Normalizing makes it a plain
+        /// space
+        Space,
+        /// a combining accent stored after its letter (NFD) rather than as one character (NFC).
+        /// Normalizing composes the two when the letter is in the Latin tables
+        Decomposed,
+        /// a Cyrillic or Greek letter among Latin ones, e.g. U+0430 CYRILLIC SMALL LETTER A.
+        /// Normalizing replaces it with the Latin letter
+        Lookalike,
+    }
+
+    /// a suspicious character in an SSID or password
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SuspiciousChar {
+        pub suspicion: Suspicion,
+        /// the character as found
+        pub ch: char,
+        /// the position of the character in the value, counting characters from 1
+        pub position: usize,
+        /// what normalize_text() writes in its place: a plain space, the composed letter (in place
+        /// of both the letter and the accent) or the Latin letter. None for invisible characters,
+        /// which are removed, and for accents without a known composition, which are kept
+        pub replacement: Option<char>,
+    }
+
+    impl SuspiciousChar {
+        /// whether normalize_text() repairs the character. Only accents it can't compose are kept
+        pub fn is_normalized(&self) -> bool {
+            !(self.suspicion == Suspicion::Decomposed && self.replacement.is_none())
+        }
+
+        /// describes the character by its code point and kind only, for values that must not be
+        /// revealed, such as passwords. Display also gives its name, position and the letter it
+        /// stands for, which show what the password is
+        pub fn redacted(&self) -> String {
+            let kind = match self.suspicion {
+                Suspicion::Invisible => "an invisible character",
+                Suspicion::Space => "a space other than U+0020",
+                Suspicion::Decomposed => "an accent stored apart from its letter (NFD)",
+                Suspicion::Lookalike => "a Cyrillic or Greek letter that looks like a Latin one",
+            };
+            format!("U+{:04X}, {}", self.ch as u32, kind)
+        }
+
+        /// the Unicode name of the character, if it is in the tables
+        fn name(&self) -> Option<&'static str> {
+            let named = |table: &[(char, &'static str)]| {
+                table
+                    .iter()
+                    .find(|(c, _)| *c == self.ch)
+                    .map(|(_, name)| *name)
+            };
+            match self.suspicion {
+                Suspicion::Invisible => named(INVISIBLE),
+                Suspicion::Space => named(SPACES),
+                Suspicion::Decomposed => named(COMBINING),
+                Suspicion::Lookalike => LOOKALIKES
+                    .iter()
+                    .find(|(c, _, _)| *c == self.ch)
+                    .map(|(_, _, name)| *name),
+            }
+        }
+    }
+
+    impl fmt::Display for SuspiciousChar {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "U+{:04X}", self.ch as u32)?;
+            if let Some(name) = self.name() {
+                write!(f, " {}", name)?;
+            }
+            write!(f, " at character {}", self.position)?;
+
+            match (self.suspicion, self.replacement) {
+                (Suspicion::Invisible, _) => f.write_str(", which is invisible"),
+                (Suspicion::Space, _) => f.write_str(", which looks like a space but isn't one"),
+                (Suspicion::Decomposed, composed) => {
+                    f.write_str(", an accent stored apart from its letter (NFD)")?;
+                    match composed {
+                        Some(c) => write!(f, "; composed, it is `{}` (U+{:04X})", c, c as u32),
+                        None => Ok(()),
+                    }
+                }
+                (Suspicion::Lookalike, Some(c)) => {
+                    write!(f, ", which looks like the Latin letter `{}`", c)
+                }
+                (Suspicion::Lookalike, None) => f.write_str(", which looks like a Latin letter"),
+            }
+        }
+    }
+
+    /// the precomposed form of a letter and a combining accent, if there is one in the tables
+    fn compose(base: char, mark: char) -> Option<char> {
+        COMPOSITIONS
+            .iter()
+            .find(|(b, m, _)| *b == base && *m == mark)
+            .map(|(_, _, composed)| *composed)
+    }
+
+    /// whether a character continues a word: letters and digits, and the accents and invisible
+    /// characters that can sit inside one
+    fn in_word(ch: char) -> bool {
+        ch.is_alphanumeric()
+            || ('\u{300}'..='\u{36F}').contains(&ch)
+            || INVISIBLE.iter().any(|(c, _)| *c == ch)
+    }
+
+    /// for each character, whether the word it is in also has Latin letters. Look-alikes only
+    /// matter when they are mixed into a Latin word, so `WiFi Гость` is two honest words
+    fn latin_words(chars: &[char]) -> Vec<bool> {
+        let mut latin = vec![false; chars.len()];
+        let mut start = 0;
+
+        while start < chars.len() {
+            let end = chars[start..]
+                .iter()
+                .position(|&c| !in_word(c))
+                .map_or(chars.len(), |len| start + len);
+            let has_latin = chars[start..end].iter().any(|c| c.is_ascii_alphabetic());
+            latin[start..end].iter_mut().for_each(|l| *l = has_latin);
+            start = end + 1;
+        }
+
+        latin
+    }
+
+    /// finds invisible characters, unusual spaces, detached accents and (in words that also
+    /// contain Latin letters) Cyrillic and Greek look-alikes. A word written entirely in Cyrillic
+    /// or Greek is not reported
+    pub fn find_suspicious(value: &str) -> Vec<SuspiciousChar> {
+        let chars: Vec<char> = value.chars().collect();
+        let latin = latin_words(&chars);
+        let mut found = Vec::new();
+        // the letter an accent applies to, after composing any accents before it
+        let mut base: Option<char> = None;
+
+        for (i, &ch) in chars.iter().enumerate() {
+            let suspect = |suspicion, replacement| SuspiciousChar {
+                suspicion,
+                ch,
+                position: i + 1,
+                replacement,
+            };
+
+            if INVISIBLE.iter().any(|(c, _)| *c == ch) {
+                let joiner = ch == '\u{200C}' || ch == '\u{200D}';
+                let between_non_ascii = i > 0
+                    && !chars[i - 1].is_ascii()
+                    && chars.get(i + 1).map(|c| !c.is_ascii()).unwrap_or(false);
+                if !(joiner && between_non_ascii) {
+                    found.push(suspect(Suspicion::Invisible, None));
+                }
+                continue;
+            }
+
+            if SPACES.iter().any(|(c, _)| *c == ch) {
+                found.push(suspect(Suspicion::Space, Some(' ')));
+                base = None;
+                continue;
+            }
+
+            if ('\u{300}'..='\u{36F}').contains(&ch) {
+                let composed = base.and_then(|b| compose(b, ch));
+                found.push(suspect(Suspicion::Decomposed, composed));
+                base = composed;
+                continue;
+            }
+
+            match LOOKALIKES.iter().find(|(c, _, _)| *c == ch) {
+                Some((_, latin_letter, _)) if latin[i] => {
+                    found.push(suspect(Suspicion::Lookalike, Some(*latin_letter)));
+                    base = Some(*latin_letter);
+                }
+                _ => base = Some(ch),
+            }
+        }
+
+        found
+    }
+
+    /// repairs what find_suspicious() reports: invisible characters are removed, unusual spaces
+    /// become U+0020, detached accents are composed with their letter where the tables know the
+    /// result, and look-alikes become the Latin letter. Anything else is left as it is
+    pub fn normalize_text(value: &str) -> String {
+        let suspicious = find_suspicious(value);
+        let mut normalized = String::with_capacity(value.len());
+
+        for (i, ch) in value.chars().enumerate() {
+            match suspicious.iter().find(|s| s.position == i + 1) {
+                None => normalized.push(ch),
+                Some(s) => match (s.suspicion, s.replacement) {
+                    (Suspicion::Invisible, _) => {}
+                    (Suspicion::Decomposed, Some(composed)) => {
+                        normalized.pop();
+                        normalized.push(composed);
+                    }
+                    (_, Some(replacement)) => normalized.push(replacement),
+                    (_, None) => normalized.push(ch),
+                },
+            }
+        }
+
+        normalized
+    }
+}
@@ -1,12 +1,19 @@
-extern crate qrcodegen;
-
 pub mod methods {
-    use qrcodegen::QrCode;
-
+    #[cfg(feature = "raster")]
     use std::convert::TryInto;
+    use std::io::{self, Write};
+    #[cfg(feature = "raster")]
+    use std::path::Path;
+    use std::str::FromStr;
+
+    #[cfg(feature = "raster")]
+    use image::codecs::png::PngEncoder;
+    #[cfg(feature = "raster")]
+    use image::{ColorType, ImageBuffer, ImageEncoder, LumaA};
 
-    use image::{ImageBuffer, LumaA};
-    use imageproc::{drawing::draw_filled_rect_mut, rect::Rect};
+    use crate::matrix::methods::QrMatrix;
+    #[cfg(feature = "raster")]
+    use crate::render::methods::{pack_bits, RasterLayout};
 
     /// returns an ImageBuffer<> that can be saved using save_image(), or passed on
     /// for further manipulation by the caller
@@ -17,48 +24,23 @@ pub mod methods {
     /// * scale: The scaling factor to apply to the qrcode
     ///
     /// * border_size: How large to make the quiet zone
+    #[cfg(feature = "raster")]
     pub fn make_image(
-        qrcode: &QrCode,
+        qrcode: &QrMatrix,
         scale: i32,
         border_size: i32,
     ) -> ImageBuffer<LumaA<u8>, Vec<u8>> {
-        let new_qr_size = qrcode.size() * scale;
-
-        // --- Initialize to a white canvas with the alpha layer pre-set ---
-        let mut image = ImageBuffer::from_pixel(
-            (new_qr_size + border_size * 2).try_into().unwrap(),
-            (new_qr_size + border_size * 2).try_into().unwrap(),
-            LumaA([255, 255]),
-        );
-
-        // --- Draw QR w/scale ---
-        for y in 0..new_qr_size {
-            for x in 0..new_qr_size {
-                if qrcode.get_module(x, y) {
-                    draw_filled_rect_mut(
-                        &mut image,
-                        Rect::at(
-                            (x * scale) + border_size as i32,
-                            (y * scale) + border_size as i32,
-                        )
-                        .of_size(scale as u32, scale as u32),
-                        LumaA([0, 255]),
-                    );
-                } else {
-                    draw_filled_rect_mut(
-                        &mut image,
-                        Rect::at(
-                            (x * scale) + border_size as i32,
-                            (y * scale) + border_size as i32,
-                        )
-                        .of_size(scale as u32, scale as u32),
-                        LumaA([255, 255]),
-                    );
-                }
-            }
-        }
+        let module_size: u32 = scale.try_into().unwrap();
+        let offset: u32 = border_size.try_into().unwrap();
+        let layout = RasterLayout {
+            size: qrcode.size() as u32 * module_size + offset * 2,
+            module_size,
+            offset,
+        };
 
-        image
+        // --- Dark modules on a white canvas, with the alpha layer pre-set ---
+        let buffer = layout.fill(qrcode, &[0, 255], &[255, 255]);
+        ImageBuffer::from_raw(layout.size, layout.size, buffer).unwrap()
     }
 
     /// saves an image to a file
@@ -66,9 +48,10 @@ pub mod methods {
     /// * image: ImageBuffer<>
     ///
     /// * save_file: file path to save the image into. ImageBuffer only supports jpeg and png extensions.
-    pub fn save_image(
+    #[cfg(feature = "raster")]
+    pub fn save_image<P: AsRef<Path>>(
         image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
-        save_file: String,
+        save_file: P,
     ) -> Result<(), image::ImageError> {
         match image.save(save_file) {
             Ok(()) => Ok(()),
@@ -76,13 +59,265 @@ pub mod methods {
         }
     }
 
+    /// writes an image into any io::Write as a PNG file
+    ///
+    /// * image: ImageBuffer<>
+    ///
+    /// * out: where to write the PNG data, e.g. a file, socket, buffer or stdout
+    #[cfg(feature = "raster")]
+    pub fn write_png<W: Write + ?Sized>(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+        out: &mut W,
+    ) -> Result<(), image::ImageError> {
+        PngEncoder::new(out).write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ColorType::La8,
+        )
+    }
+
+    /// writes an image into any io::Write as a 1-bit grayscale PNG file, which is far smaller than
+    /// write_png() for a two-color image
+    ///
+    /// * image: ImageBuffer<>, e.g. from make_image(). Pixels darker than 50% gray are written black,
+    ///   the rest white
+    ///
+    /// * transparent: make the white pixels transparent, with a tRNS chunk
+    ///
+    /// * out: where to write the PNG data
+    #[cfg(feature = "raster")]
+    pub fn write_png_1bit<W: Write + ?Sized>(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+        transparent: bool,
+        out: &mut W,
+    ) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(out, image.width(), image.height());
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::One);
+        encoder.set_compression(png::Compression::Best);
+        if transparent {
+            encoder.set_trns(vec![0, 1]);
+        }
+
+        let pixels: Vec<u8> = image.pixels().map(|p| (p[0] >= 128) as u8).collect();
+        encoder
+            .write_header()?
+            .write_image_data(&pack_bits(image.width(), &pixels))
+    }
+
+    /// the block characters used to draw a QR code on a terminal
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ConsoleStyle {
+        /// two full blocks (`██`) per module, one line per row of modules
+        Full,
+        /// upper/lower half blocks (`▀`, `▄`), one character per module and one line per two rows.
+        /// Modules stay square on terminals with the usual 1:2 cell aspect ratio.
+        Half,
+        /// quadrant blocks (`▘`, `▞`, `▟`, ...), one character per 2x2 modules. This is the most
+        /// compact style, but modules are drawn twice as tall as they are wide.
+        Quadrant,
+    }
+
+    impl FromStr for ConsoleStyle {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "full" => Ok(ConsoleStyle::Full),
+                "half" => Ok(ConsoleStyle::Half),
+                "quadrant" => Ok(ConsoleStyle::Quadrant),
+                _ => Err(format!(
+                    "Unknown console style: {}. Try one of: full, half, quadrant",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// quadrant block characters, indexed by a bitmask of the dark modules in a 2x2 cell:
+    /// top-left = 1, top-right = 2, bottom-left = 4, bottom-right = 8
+    const QUADRANT_BLOCKS: [char; 16] = [
+        ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+    ];
+
+    /// ANSI escape that selects a black foreground on a bright white background
+    const ANSI_BLACK_ON_WHITE: &str = "\x1b[30;107m";
+    /// ANSI escape that selects a bright white foreground on a black background
+    const ANSI_WHITE_ON_BLACK: &str = "\x1b[97;40m";
+    /// ANSI escape that resets all colors and attributes
+    const ANSI_RESET: &str = "\x1b[0m";
+
+    /// options controlling how a QR code is drawn on a terminal
+    ///
+    /// Block characters are drawn in the terminal's foreground color, so whether a code comes out
+    /// right depends on the terminal theme. `invert` flips which modules are drawn, and
+    /// `ansi_colors` sets the colors explicitly so the code (and its quiet zone) is dark-on-light
+    /// regardless of theme. `ansi_colors` is off in the default, since escapes are noise in files
+    /// and pipes; console_qr() and the wifiqr utility turn it on when stdout is a terminal.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ConsoleOptions {
+        /// the border size to apply to the QR code, in modules
+        pub quiet_zone: i32,
+        /// the block characters to draw with
+        pub style: ConsoleStyle,
+        /// draw block characters for light modules instead of dark ones
+        pub invert: bool,
+        /// wrap every line in ANSI color escapes so that dark modules are black and light
+        /// modules, including the quiet zone, are white
+        pub ansi_colors: bool,
+    }
+
+    impl Default for ConsoleOptions {
+        fn default() -> Self {
+            ConsoleOptions {
+                quiet_zone: 2,
+                style: ConsoleStyle::Full,
+                invert: false,
+                ansi_colors: false,
+            }
+        }
+    }
+
+    /// returns the lines of text that draw a QR code on a terminal
+    ///
+    /// * qrcode: Is an encoded qrcode
+    ///
+    /// * options: quiet zone, block characters and colors to draw with
+    pub fn console_lines(qrcode: &QrMatrix, options: &ConsoleOptions) -> Vec<String> {
+        let start = -options.quiet_zone;
+        let end = qrcode.size() + options.quiet_zone;
+
+        // get_module() already treats coordinates outside the symbol as light modules,
+        // which takes care of painting the quiet zone. "drawn" modules get a block character
+        let drawn = |x: i32, y: i32| qrcode.get_module(x, y) != options.invert;
+
+        let mut lines = Vec::new();
+
+        match options.style {
+            ConsoleStyle::Full => {
+                for y in start..end {
+                    lines.push(
+                        (start..end)
+                            .map(|x| if drawn(x, y) { "██" } else { "  " })
+                            .collect(),
+                    );
+                }
+            }
+            ConsoleStyle::Half => {
+                for y in (start..end).step_by(2) {
+                    lines.push(
+                        (start..end)
+                            .map(|x| match (drawn(x, y), drawn(x, y + 1)) {
+                                (true, true) => '█',
+                                (true, false) => '▀',
+                                (false, true) => '▄',
+                                (false, false) => ' ',
+                            })
+                            .collect(),
+                    );
+                }
+            }
+            ConsoleStyle::Quadrant => {
+                for y in (start..end).step_by(2) {
+                    lines.push(
+                        (start..end)
+                            .step_by(2)
+                            .map(|x| {
+                                let index = drawn(x, y) as usize
+                                    | (drawn(x + 1, y) as usize) << 1
+                                    | (drawn(x, y + 1) as usize) << 2
+                                    | (drawn(x + 1, y + 1) as usize) << 3;
+                                QUADRANT_BLOCKS[index]
+                            })
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        if options.ansi_colors {
+            // with invert the glyphs are the light modules, so they get the light color
+            let colors = if options.invert {
+                ANSI_WHITE_ON_BLACK
+            } else {
+                ANSI_BLACK_ON_WHITE
+            };
+
+            for line in lines.iter_mut() {
+                *line = format!("{}{}{}", colors, line, ANSI_RESET);
+            }
+        }
+
+        lines
+    }
+
+    /// writes a QR code, drawn with block characters, into any io::Write
+    ///
+    /// * qrcode: Is an encoded qrcode
+    ///
+    /// * options: quiet zone, block characters and colors to draw with
+    ///
+    /// * out: where to write the text, e.g. stdout or a buffer
+    pub fn write_console<W: Write + ?Sized>(
+        qrcode: &QrMatrix,
+        options: &ConsoleOptions,
+        out: &mut W,
+    ) -> io::Result<()> {
+        for line in console_lines(qrcode, options) {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// prints a QR code to the console
+    ///
+    /// * qrcode: Is an encoded qrcode
+    ///
+    /// * options: quiet zone, block characters and colors to draw with
+    pub fn console_qr(qrcode: &QrMatrix, options: &ConsoleOptions) {
+        write_console(qrcode, options, &mut io::stdout().lock()).expect("failed printing to stdout")
+    }
+
     /// returns a QR code that can be interpreted by an SVG reader
     ///
-    /// * qr: &QrCode
+    /// * qr: &QrMatrix
     ///
     /// * border: size of border to apply to the SVG
-    pub fn to_svg_string(qr: &QrCode, border: i32) -> String {
-        /* to_svg_string is derived from Project Nayuki's QR Code Generator
+    #[cfg(feature = "svg")]
+    pub fn to_svg_string(qr: &QrMatrix, border: i32) -> String {
+        let mut svg = Vec::new();
+        // writing into a Vec<u8> cannot fail, and write_svg only emits UTF-8
+        write_svg(qr, border, &mut svg).unwrap();
+        String::from_utf8(svg).unwrap()
+    }
+
+    /// writes a QR code that can be interpreted by an SVG reader into any io::Write
+    ///
+    /// * qr: &QrMatrix
+    ///
+    /// * border: size of border to apply to the SVG
+    ///
+    /// * out: where to write the SVG document
+    #[cfg(feature = "svg")]
+    pub fn write_svg<W: Write + ?Sized>(qr: &QrMatrix, border: i32, out: &mut W) -> io::Result<()> {
+        write_svg_colored(qr, border, "#000000", "#FFFFFF", out)
+    }
+
+    /// like write_svg, with custom colors for the dark and light modules
+    ///
+    /// * dark: an SVG color (e.g. `#000000`) for the dark modules
+    ///
+    /// * light: an SVG color (e.g. `#FFFFFF`) for the light modules and the border
+    #[cfg(feature = "svg")]
+    pub fn write_svg_colored<W: Write + ?Sized>(
+        qr: &QrMatrix,
+        border: i32,
+        dark: &str,
+        light: &str,
+        out: &mut W,
+    ) -> io::Result<()> {
+        /* write_svg is derived from Project Nayuki's QR Code Generator
          *
          * Copyright (c) Project Nayuki. (MIT License)
          * https://www.nayuki.io/page/qr-code-generator-library
@@ -106,29 +341,34 @@ pub mod methods {
          */
 
         assert!(border >= 0, "Border must be non-negative");
-        let mut result = String::new();
-        result += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
-        result += "<!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd\">\n";
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd\">")?;
         let dimension = qr
             .size()
             .checked_add(border.checked_mul(2).unwrap())
             .unwrap();
-        result += &format!(
-	        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 {0} {0}\" stroke=\"none\">\n", dimension);
-        result += "\t<rect width=\"100%\" height=\"100%\" fill=\"#FFFFFF\"/>\n";
-        result += "\t<path d=\"";
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 {0} {0}\" stroke=\"none\">",
+            dimension
+        )?;
+        writeln!(
+            out,
+            "\t<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            light
+        )?;
+        write!(out, "\t<path d=\"")?;
         for y in 0..qr.size() {
             for x in 0..qr.size() {
                 if qr.get_module(x, y) {
                     if x != 0 || y != 0 {
-                        result += " ";
+                        write!(out, " ")?;
                     }
-                    result += &format!("M{},{}h1v1h-1z", x + border, y + border);
+                    write!(out, "M{},{}h1v1h-1z", x + border, y + border)?;
                 }
             }
         }
-        result += "\" fill=\"#000000\"/>\n";
-        result += "</svg>\n";
-        result
+        writeln!(out, "\" fill=\"{}\"/>", dark)?;
+        writeln!(out, "</svg>")
     }
 }
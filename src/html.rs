@@ -0,0 +1,216 @@
+/// Self-contained HTML guest pages
+///
+/// A single file with the code as inline SVG, the network name, an optional password with a copy
+/// button and instructions for joining from common platforms. The page has no external resources,
+/// so it can be posted on a wiki, shown on a lobby screen or printed on A4 or Letter paper.
+pub mod methods {
+    use std::error;
+    use std::io::Write;
+
+    use crate::code::Credentials;
+    use crate::matrix::methods::QrMatrix;
+    use crate::svg::methods::{escape_xml, write_svg_element, SvgOptions};
+
+    /// the page layout. The printed area is sized to fit both A4 (210x297mm) and Letter
+    /// (216x279mm) paper with 15mm margins
+    const STYLE: &str = r#"
+    body { font-family: system-ui, -apple-system, "Segoe UI", Roboto, sans-serif; color: #111; background: #fff; margin: 0; }
+    main { max-width: 40rem; margin: 0 auto; padding: 2rem 1.5rem; text-align: center; }
+    h1 { font-size: 2rem; margin: 0 0 1.5rem; }
+    .code svg { width: 100%; max-width: 22rem; height: auto; }
+    dl { display: grid; grid-template-columns: auto 1fr; gap: 0.5rem 1rem; max-width: 26rem; margin: 1.5rem auto; text-align: left; font-size: 1.25rem; }
+    dt { font-weight: 600; }
+    dd { margin: 0; overflow-wrap: anywhere; }
+    code { font-size: 1.25rem; }
+    button { margin-left: 0.5rem; font: inherit; font-size: 1rem; padding: 0.2rem 0.75rem; cursor: pointer; }
+    .instructions { text-align: left; max-width: 32rem; margin: 2rem auto 0; }
+    .instructions h2 { font-size: 1.25rem; }
+    .instructions li { margin-bottom: 0.5rem; }
+    @page { margin: 15mm; }
+    @media print {
+        main { max-width: 180mm; padding: 0; }
+        h1 { font-size: 24pt; }
+        .code svg { width: 110mm; max-width: none; }
+        dl, code { font-size: 14pt; }
+        .instructions { font-size: 11pt; margin-top: 8mm; }
+        button { display: none; }
+        main { page-break-inside: avoid; break-inside: avoid; }
+    }
+"#;
+
+    /// copies the password. navigator.clipboard needs a secure context, which pages opened from
+    /// disk are not, so older execCommand() is used as a fallback
+    const SCRIPT: &str = r#"
+    function copyPassword(button) {
+        var password = document.getElementById("password");
+        var done = function () { button.textContent = "Copied"; };
+        if (navigator.clipboard && window.isSecureContext) {
+            navigator.clipboard.writeText(password.textContent).then(done);
+            return;
+        }
+        var range = document.createRange();
+        range.selectNodeContents(password);
+        var selection = window.getSelection();
+        selection.removeAllRanges();
+        selection.addRange(range);
+        if (document.execCommand("copy")) { done(); }
+        selection.removeAllRanges();
+    }
+"#;
+
+    /// what goes on an HTML guest page
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct HtmlOptions {
+        /// show the password, with a copy button, and include it in the code's description
+        pub include_password: bool,
+        /// the page heading. Defaults to "Wi-Fi" and the network name
+        pub heading: Option<String>,
+        /// how the code itself is drawn. Its title and description are filled in from the network
+        pub svg: SvgOptions,
+    }
+
+    /// a human-readable name for an encryption type
+    fn security_name(encr: &str) -> String {
+        match encr.to_lowercase().as_str() {
+            "" | "nopass" => "None (open network)".to_string(),
+            _ => encr.to_uppercase(),
+        }
+    }
+
+    /// writes the join instructions, as a list per platform
+    fn write_instructions(
+        config: &Credentials,
+        include_password: bool,
+        out: &mut dyn Write,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let ssid = escape_xml(&config.ssid);
+        let has_password = !config.pass.is_empty();
+
+        let password_step = if !has_password {
+            "No password is needed."
+        } else if include_password {
+            "Enter the password shown above."
+        } else {
+            "Enter the network password."
+        };
+
+        let pick_network = if config.hidden {
+            format!(
+                "The network is hidden: choose <em>Other network</em> (or <em>Add network</em>) and type the name <strong>{}</strong>.",
+                ssid
+            )
+        } else {
+            format!(
+                "Choose <strong>{}</strong> from the list of networks.",
+                ssid
+            )
+        };
+
+        writeln!(out, "      <section class=\"instructions\">")?;
+        writeln!(out, "        <h2>How to join</h2>")?;
+
+        writeln!(out, "        <h3>iPhone and iPad</h3>")?;
+        writeln!(out, "        <ol>")?;
+        writeln!(
+            out,
+            "          <li>Open the <em>Camera</em> app and point it at the code.</li>"
+        )?;
+        writeln!(
+            out,
+            "          <li>Tap the <em>Join network</em> notification that appears.</li>"
+        )?;
+        writeln!(out, "        </ol>")?;
+
+        writeln!(out, "        <h3>Android</h3>")?;
+        writeln!(out, "        <ol>")?;
+        writeln!(out, "          <li>Open the <em>Camera</em> app (or <em>Google Lens</em>) and point it at the code.</li>")?;
+        writeln!(out, "          <li>Tap <em>Connect</em>. On older phones, open <em>Settings</em> &rsaquo; <em>Wi-Fi</em> and tap the QR code icon next to <em>Add network</em> instead.</li>")?;
+        writeln!(out, "        </ol>")?;
+
+        writeln!(out, "        <h3>Laptops and other devices</h3>")?;
+        writeln!(out, "        <ol>")?;
+        writeln!(out, "          <li>Open the Wi-Fi menu or settings.</li>")?;
+        writeln!(out, "          <li>{}</li>", pick_network)?;
+        writeln!(out, "          <li>{}</li>", password_step)?;
+        writeln!(out, "        </ol>")?;
+
+        writeln!(out, "      </section>")?;
+        Ok(())
+    }
+
+    /// writes a complete HTML page for joining a network
+    ///
+    /// * qr: the encoded credentials, e.g. from encode()
+    ///
+    /// * config: the network shown on the page
+    ///
+    /// * options: see HtmlOptions. Callers should check the SVG style, and its colors, with
+    ///   SvgStyle::check() first.
+    ///
+    /// * out: where to write the page
+    pub fn write_html_page(
+        qr: &QrMatrix,
+        config: &Credentials,
+        options: &HtmlOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let ssid = escape_xml(&config.ssid);
+        let heading = match &options.heading {
+            Some(heading) => escape_xml(heading),
+            None => format!("Wi-Fi: {}", ssid),
+        };
+        let show_password = options.include_password && !config.pass.is_empty();
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html lang=\"en\">")?;
+        writeln!(out, "  <head>")?;
+        writeln!(out, "    <meta charset=\"utf-8\">")?;
+        writeln!(
+            out,
+            "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+        )?;
+        writeln!(out, "    <title>{}</title>", heading)?;
+        writeln!(out, "    <style>{}    </style>", STYLE)?;
+        if show_password {
+            writeln!(out, "    <script>{}    </script>", SCRIPT)?;
+        }
+        writeln!(out, "  </head>")?;
+        writeln!(out, "  <body>")?;
+        writeln!(out, "    <main>")?;
+        writeln!(out, "      <h1>{}</h1>", heading)?;
+
+        writeln!(out, "      <div class=\"code\">")?;
+        let svg = options
+            .svg
+            .clone()
+            .with_network(config, options.include_password);
+        write_svg_element(qr, &svg, out)?;
+        writeln!(out, "      </div>")?;
+
+        writeln!(out, "      <dl>")?;
+        writeln!(out, "        <dt>Network</dt>")?;
+        writeln!(out, "        <dd><code>{}</code></dd>", ssid)?;
+        writeln!(out, "        <dt>Security</dt>")?;
+        writeln!(
+            out,
+            "        <dd>{}</dd>",
+            escape_xml(&security_name(&config.encr))
+        )?;
+        if show_password {
+            writeln!(out, "        <dt>Password</dt>")?;
+            writeln!(
+                out,
+                "        <dd><code id=\"password\">{}</code><button type=\"button\" onclick=\"copyPassword(this)\">Copy</button></dd>",
+                escape_xml(&config.pass)
+            )?;
+        }
+        writeln!(out, "      </dl>")?;
+
+        write_instructions(config, options.include_password, out)?;
+
+        writeln!(out, "    </main>")?;
+        writeln!(out, "  </body>")?;
+        writeln!(out, "</html>")?;
+        Ok(())
+    }
+}
@@ -1,37 +1,136 @@
+// tests always link std for the harness; `cargo build --no-default-features` checks no_std itself
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
 /// wifiqr
 /// A crate to transform Wifi credentials into a scannable QR code
+extern crate alloc;
+#[cfg(feature = "raster")]
 extern crate image;
+#[cfg(feature = "std")]
 extern crate qrcodegen;
 
+#[cfg(feature = "std")]
+mod bitmap;
+mod confusable;
+#[cfg(feature = "std")]
 mod exporters;
+#[cfg(feature = "svg")]
+mod html;
+#[cfg(feature = "std")]
+mod lint;
+#[cfg(feature = "std")]
+mod matrix;
+#[cfg(feature = "std")]
+mod onc;
+mod profile;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "std")]
+mod style;
+#[cfg(feature = "svg")]
+mod svg;
+#[cfg(feature = "raster")]
+mod terminal;
+mod wifi;
 
-macro_rules! wifi_auth {
-    // Derived from:
-    // https://github.com/zxing/zxing/wiki/Barcode-Contents#wifi-network-config-android
-    //
-    // T: authentication type (WEP, WPA, 'nopass'). Can be ommitted for no password.
-    // S: network SSID
-    // P: wifi password. Can be ommitted if T is 'nopass'
-    // H: Hidden SSID. Optional.
-    (hidden) => {
-        "WIFI:T:{};S:{};P:{};H:{};;"
-    };
-    (nopass) => {
-        "WIFI:T:nopass;S:{};;"
-    };
-    (nopass_hidden) => {
-        "WIFI:T:nopass;S:{};H:{};;"
-    };
-    () => {
-        "WIFI:T:{};S:{};P:{};;"
+/// The Wi-Fi payload on its own: Credentials and the `WIFI:` string from Credentials::format().
+/// Unlike `code`, this builds with `no_std` and `alloc`, e.g. for firmware that draws the code with
+/// its own encoder
+pub mod payload {
+    pub use crate::confusable::methods::{
+        find_suspicious, normalize_text, Suspicion, SuspiciousChar,
     };
+    pub use crate::profile::methods::{FieldOrder, Profile};
+    #[cfg(feature = "serde")]
+    pub use crate::wifi::methods::RedactedCredentials;
+    pub use crate::wifi::methods::{auth, Credentials, FormatError, SCHEMA_VERSION};
 }
 
+#[cfg(feature = "std")]
+pub use crate::matrix::methods::{ErrorCorrection, QrMatrix};
+
+/// tests that run with any set of features, so that the payload-only (`--no-default-features`)
+/// and `std`-only builds are checked too
 #[cfg(test)]
-mod tests {
-    use super::code::Credentials;
-    use super::code::{encode, make_svg, manual_encode};
-    use qrcodegen::{QrCodeEcc, Version};
+mod payload_tests {
+    use super::payload::{auth, normalize_text, Credentials, Profile};
+
+    /// the payload builds, escapes and validates networks without the encoder
+    #[test]
+    fn test_payload_only() {
+        let config = auth(
+            Some("Café;Bar"),
+            Some("pass\"word"),
+            Some("wpa2"),
+            true,
+            false,
+        );
+        assert_eq!(
+            config.format().unwrap(),
+            r#"WIFI:T:WPA2;S:Café\;Bar;P:pass\"word;H:true;;"#
+        );
+
+        assert!(
+            auth(Some("open"), Some("secret"), Some("nopass"), false, false)
+                .format()
+                .is_err()
+        );
+        assert!(auth(Some("guest"), Some(""), Some("wpa2"), false, false)
+            .format()
+            .is_err());
+        assert!(auth(
+            Some(&"x".repeat(33)),
+            Some("password"),
+            Some("wpa2"),
+            false,
+            false
+        )
+        .format()
+        .is_err());
+
+        let mut android = auth(Some("CAFE"), Some("password"), Some("wpa2"), false, false);
+        android.profile = Profile::Android;
+        assert_eq!(
+            android.format().unwrap(),
+            r#"WIFI:S:"CAFE";T:wpa2;P:password;H:false;;"#
+        );
+
+        let raw = Credentials::new(Some(""), Some(""), Some("nopass"), false, false)
+            .with_ssid_bytes(&[0xca, 0xfe, 0xff]);
+        assert_eq!(raw.format().unwrap(), "WIFI:T:nopass;S:CAFEFF;;");
+
+        assert_eq!(normalize_text("Guest\u{200B}"), "Guest");
+        let pasted = auth(
+            Some("Guest\u{A0}Net"),
+            Some("password"),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        assert_eq!(pasted.warnings().len(), 1);
+        assert_eq!(
+            pasted.normalized().format().unwrap(),
+            "WIFI:T:WPA2;S:Guest Net;P:password;;"
+        );
+    }
+
+    /// the encoder, matrix and lint work without any output format
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_only() {
+        use super::code::{encode, lint, module_matrix};
+
+        let config = auth(Some("guest"), Some("password"), Some("wpa2"), false, false);
+        let qr = encode(&config).unwrap();
+        assert_eq!(qr.version(), 4);
+
+        let matrix = module_matrix(&qr, 4);
+        assert_eq!(matrix.len() as i32, qr.size() + 8);
+        // the top-left finder pattern starts right inside the quiet zone
+        assert!(matrix[4][4] && !matrix[3][3]);
+
+        assert!(lint(&config).is_empty());
+    }
 
     /// Basic functionality test
     #[test]
@@ -61,23 +160,6 @@ mod tests {
         );
     }
 
-    /// Exercise the automatic qr encoder against the manual encoder
-    #[test]
-    fn test_qrcodes() {
-        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
-
-        assert_eq!(
-            make_svg(&encode(&credentials).unwrap()),
-            make_svg(&manual_encode(
-                &credentials,
-                QrCodeEcc::High,
-                Version::new(2),
-                Version::new(15),
-                None,
-            ))
-        );
-    }
-
     /// Ensure that the hidden flag is added if requested
     #[test]
     fn test_hidden_ssid() {
@@ -253,279 +335,2024 @@ mod tests {
     }
 }
 
-/// Wifi QR code generator
-pub mod code {
-    use std::error;
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[cfg(any(feature = "raster", feature = "svg"))]
+    use super::code::Color;
+    #[cfg(any(feature = "raster", feature = "serde"))]
+    use super::code::PngMode;
+    use super::code::{
+        encode, find_suspicious, from_hex, from_onc, lint, lint_payload, manual_encode,
+        module_matrix, normalize_text, pack_bitmap, render, render_data_uri, to_onc, write_console,
+        write_matrix,
+    };
+    #[cfg(feature = "svg")]
+    use super::code::{
+        make_html, make_styled_svg, make_svg, make_svg_data_uri, make_svg_with, write_svg,
+        HtmlOptions, SvgOptions,
+    };
+    #[cfg(feature = "raster")]
+    use super::code::{
+        make_image, make_image_data_uri, make_image_with, to_kitty, to_sixel, write_png,
+        write_png_1bit,
+    };
+    use super::code::{
+        BitmapLayout, ConsoleOptions, ConsoleStyle, Credentials, Issue, IssueKind, MatrixFormat,
+        Profile, Registry, RenderOptions, Severity, Suspicion, SuspiciousChar,
+    };
+    #[cfg(feature = "svg")]
+    use super::code::{
+        FinderShape, Gradient, Length, ModuleShape, PathMode, Renderer, SvgStyle, Unit,
+    };
+    use super::exporters::methods::console_lines;
+    use super::{ErrorCorrection, QrMatrix};
 
-    use image::{ImageBuffer, LumaA};
-    use qrcodegen::{Mask, QrCode, QrCodeEcc, QrSegment};
+    /// Exercise the automatic qr encoder against the manual encoder
+    #[test]
+    fn test_qrcodes() {
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
 
-    use crate::exporters::methods::{
-        make_image as make_image_export, save_image as save_image_export,
-        to_svg_string as to_svg_string_export,
-    };
+        assert_eq!(
+            encode(&credentials).unwrap(),
+            manual_encode(&credentials, ErrorCorrection::High, 2, 15, None).unwrap()
+        );
+    }
 
-    #[derive(Debug)]
-    pub struct Credentials {
-        pub ssid: String,
-        pub pass: String,
-        pub encr: String,
-        pub hidden: bool,
-        pub quote: bool,
-    }
-
-    impl Credentials {
-        pub fn new(
-            mut _ssid: Option<&str>,
-            mut _password: Option<&str>,
-            mut _encr: Option<&str>,
-            mut _hidden: bool,
-            mut _quote: bool,
-        ) -> Self {
-            Credentials {
-                ssid: _ssid.unwrap().to_string(),
-                encr: _encr.unwrap().to_string(),
-                pass: _password.unwrap().to_string(),
-                hidden: _hidden,
-                quote: _quote,
-            }
+    /// ONC output should round-trip back into the same credentials
+    #[test]
+    fn test_onc_round_trip() {
+        let credentials =
+            Credentials::new(Some("guest"), Some("password"), Some("wpa2"), true, false);
+        let onc = to_onc(&credentials).unwrap();
+
+        assert!(onc.contains(r#""Security": "WPA-PSK""#));
+        assert!(onc.contains(r#""HexSSID": "6775657374""#));
+
+        let imported = from_onc(&onc).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(
+            imported[0].format().unwrap(),
+            "WIFI:T:WPA2;S:guest;P:password;H:true;;"
+        );
+    }
+
+    /// ONC imports should prefer HexSSID, skip non-WiFi entries and map security types
+    #[test]
+    fn test_onc_import() {
+        let onc = r#"{
+            "Type": "UnencryptedConfiguration",
+            "NetworkConfigurations": [
+                { "GUID": "eth", "Type": "Ethernet", "Ethernet": {} },
+                { "GUID": "a", "Type": "WiFi",
+                  "WiFi": { "SSID": "ignored", "HexSSID": "6C6F626279", "Security": "WEP-PSK", "Passphrase": "abcde" } },
+                { "GUID": "b", "Type": "WiFi",
+                  "WiFi": { "SSID": "open", "Security": "None", "HiddenSSID": true } }
+            ]
+        }"#;
+
+        let imported = from_onc(onc).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(
+            imported[0].format().unwrap(),
+            "WIFI:T:WEP;S:lobby;P:abcde;;"
+        );
+        assert_eq!(
+            imported[1].format().unwrap(),
+            "WIFI:T:nopass;S:open;H:true;;"
+        );
+
+        assert!(from_onc(r#"{"Type": "EncryptedConfiguration"}"#).is_err());
+        // enterprise networks need EAP settings that a WIFI: payload can't carry
+        assert!(
+            from_onc(r#"{"Type": "WiFi", "WiFi": {"SSID": "corp", "Security": "WPA-EAP"}}"#)
+                .is_err()
+        );
+        let corp = Credentials::new(Some("corp"), Some("secret"), Some("wpa2-eap"), false, false);
+        assert!(to_onc(&corp).is_err());
+        assert!(
+            from_onc(r#"{"Type": "WiFi", "WiFi": {"HexSSID": "+f", "Security": "None"}}"#).is_err()
+        );
+    }
+
+    /// half and quadrant console styles pack two rows of modules into each line
+    #[test]
+    fn test_console_styles() {
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
+        let qr = encode(&credentials).unwrap();
+        let width = (qr.size() + 4) as usize;
+
+        let options = |style| ConsoleOptions {
+            quiet_zone: 2,
+            style,
+            ..ConsoleOptions::default()
+        };
+
+        let full = console_lines(&qr, &options(ConsoleStyle::Full));
+        assert_eq!(full.len(), width);
+        assert!(full.iter().all(|l| l.chars().count() == width * 2));
+
+        let half = console_lines(&qr, &options(ConsoleStyle::Half));
+        assert_eq!(half.len(), width.div_ceil(2));
+        assert!(half.iter().all(|l| l.chars().count() == width));
+        // the top-left finder pattern starts in the third column, on the second half of line one
+        assert!(half[1].starts_with("  █"));
+
+        let quadrant = console_lines(&qr, &options(ConsoleStyle::Quadrant));
+        assert_eq!(quadrant.len(), width.div_ceil(2));
+        assert!(quadrant
+            .iter()
+            .all(|l| l.chars().count() == width.div_ceil(2)));
+    }
+
+    /// inverted output draws the quiet zone, and ansi output colors every line
+    #[test]
+    fn test_console_invert_and_colors() {
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
+        let qr = encode(&credentials).unwrap();
+
+        let inverted = console_lines(
+            &qr,
+            &ConsoleOptions {
+                invert: true,
+                ..ConsoleOptions::default()
+            },
+        );
+        assert!(inverted[0].chars().all(|c| c == '█'));
+
+        let colored = console_lines(
+            &qr,
+            &ConsoleOptions {
+                ansi_colors: true,
+                ..ConsoleOptions::default()
+            },
+        );
+        assert!(colored
+            .iter()
+            .all(|l| l.starts_with("\x1b[30;107m") && l.ends_with("\x1b[0m")));
+    }
+
+    /// sixel output covers the whole image in bands of six rows, and kitty output is chunked
+    #[cfg(feature = "raster")]
+    #[test]
+    fn test_terminal_graphics() {
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
+        let image = make_image(&encode(&credentials).unwrap(), 10, 20);
+        let height = image.height() as usize;
+
+        let sixel = to_sixel(&image);
+        assert!(sixel.starts_with("\x1bP0;1q\"1;1;"));
+        assert!(sixel.ends_with("\x1b\\"));
+        assert_eq!(sixel.matches('-').count(), height.div_ceil(6));
+
+        let kitty = to_kitty(&image).unwrap();
+        assert!(kitty.starts_with("\x1b_Gf=100,a=T,m="));
+        assert!(kitty.ends_with("\x1b\\"));
+        assert_eq!(kitty.matches("m=0;").count(), 1);
+
+        // the renderers share the size limits and colors of other raster output
+        let qr = encode(&credentials).unwrap();
+        let registry = Registry::default();
+        let huge = RenderOptions {
+            module_size: 100_000,
+            quiet_zone: 100_000,
+            ..RenderOptions::default()
+        };
+        let tiny = RenderOptions {
+            module_size: -3,
+            ..RenderOptions::default()
+        };
+        let inverted = RenderOptions {
+            invert: true,
+            ..RenderOptions::default()
+        };
+        let colored = RenderOptions {
+            dark_color: "#003366".parse().unwrap(),
+            ..RenderOptions::default()
+        };
+        for name in ["sixel", "kitty", "iterm"].iter() {
+            assert!(render(&qr, &registry, name, &huge, &mut Vec::new()).is_err());
+            assert!(render(&qr, &registry, name, &tiny, &mut Vec::new()).is_ok());
+            assert!(render(&qr, &registry, name, &inverted, &mut Vec::new()).is_ok());
+            assert!(render(&qr, &registry, name, &colored, &mut Vec::new()).is_err());
         }
 
-        /// escape characters as in:
-        /// https://github.com/zxing/zxing/wiki/Barcode-Contents#wifi-network-config-android
-        /// Special characters `\`, `;`, `,` and `:` should be escaped with a backslash
-        fn filter_credentials(&self, field: &str) -> String {
-            // N.B. If performance problems ever crop up, this might be more performant
-            // with regex replace_all
-
-            let mut filtered = field
-                .to_string()
-                .replace('\\', r#"\\"#)
-                .replace('"', r#"\""#)
-                .replace(';', r#"\;"#)
-                .replace(r#"':'"#, r#"\:"#);
-
-            if (filtered == self.ssid || filtered == self.pass) && self.quote {
-                // println!("Adding quotes to SSID/Password -- quote is not set");
-                filtered = format!("\"{}\"", field);
-            }
+        let mut plain = Vec::new();
+        let mut negative = Vec::new();
+        render(
+            &qr,
+            &registry,
+            "sixel",
+            &RenderOptions::default(),
+            &mut plain,
+        )
+        .unwrap();
+        render(&qr, &registry, "sixel", &inverted, &mut negative).unwrap();
+        assert_ne!(plain, negative);
+    }
+
+    /// the streaming writers should produce the same output as the String/print based exporters
+    #[test]
+    fn test_writers() {
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
+        let qr = encode(&credentials).unwrap();
 
-            filtered
+        #[cfg(feature = "svg")]
+        {
+            let mut svg = Vec::new();
+            write_svg(&qr, &mut svg).unwrap();
+            assert_eq!(String::from_utf8(svg).unwrap(), make_svg(&qr));
         }
 
-        /// the encryption field in the Wifi QR code fails on iOS devices if it is
-        /// not provided in an uppercase format. Android devices are case insensitive,
-        /// so the encryption field is passed through as uppercase now.
-        fn filter_encr(&self, field: &str) -> String {
-            if field != "nopass" && !self.encr.is_empty() {
-                return field.to_string().to_uppercase();
-            }
-            field.to_string()
+        let mut console = Vec::new();
+        let options = ConsoleOptions::default();
+        write_console(&qr, &options, &mut console).unwrap();
+        assert_eq!(
+            String::from_utf8(console).unwrap(),
+            console_lines(&qr, &options).join("\n") + "\n"
+        );
+
+        #[cfg(feature = "raster")]
+        {
+            let mut png = Vec::new();
+            write_png(&make_image(&qr, 4, 8), &mut png).unwrap();
+            assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
         }
+    }
+
+    /// renderers are found by name and extension, and later registrations win
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_registry() {
+        struct Payload;
 
-        /// Call the wifi_auth! macro to generate a qr-string and/or return any errors that
-        /// need to be raised to the caller. Note: format does not enforce an encryption type, it is
-        /// up to the end user to use the right value if one is provided.
-        pub fn format(&self) -> Result<String, FormatError> {
-            // empty password ->
-            //  * is password empty and ssid hidden? => set T:nopass and H:
-            //  * is encryption type empty? => set nopass
-            //  * hidden ssid? => add H:
-            // plain format
-            // unrecoverable errors:
-            // * ssid has no password, but sets a T type
-            // * sets a password, but sets T type to nopass
-            if (self.encr == "nopass" || self.encr.is_empty()) && !self.pass.is_empty() {
-                return Err(FormatError(
-                    "With nopass as the encryption type (or unset encryption type), 
-                    the password field should be empty. (Encryption should probably be set 
-                    to something like wpa2)".to_string(),
-                ));
+        impl Renderer for Payload {
+            fn name(&self) -> &str {
+                "svg"
             }
 
-            if self.pass.is_empty() {
-                // Error condition: Password is empty, and the T (encr) type is not "nopass" / not empty
-                if self.encr != "nopass" && !self.encr.is_empty() {
-                    return Err(FormatError("The encryption method requested requires a password.".to_string()));
-                }
+            fn extensions(&self) -> &[&str] {
+                &["svg", "wifi"]
+            }
+
+            fn render(
+                &self,
+                qrcode: &QrMatrix,
+                _options: &RenderOptions,
+                out: &mut dyn std::io::Write,
+            ) -> Result<(), Box<dyn std::error::Error>> {
+                write!(out, "{}", qrcode.size())?;
+                Ok(())
+            }
+        }
+
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
+        let qr = encode(&credentials).unwrap();
+        let options = RenderOptions::default();
 
-                if self.encr.is_empty() || self.encr == "nopass" {
-                    if self.hidden {
-                        return Ok(format!(
-                            wifi_auth!(nopass_hidden),
-                            self.filter_credentials(&self.ssid),
-                            &self.hidden,
-                        ));
-                    } else if self.pass.is_empty() {
-                        return Ok(format!(
-                            wifi_auth!(nopass),
-                            self.filter_credentials(&self.ssid),
-                        ));
+        let mut registry = Registry::default();
+        #[cfg(feature = "raster")]
+        {
+            assert_eq!(registry.for_extension("JPG").unwrap().name(), "jpeg");
+            assert_eq!(registry.for_path("qr.TIF").unwrap().name(), "tiff");
+        }
+        assert!(registry.for_path("qr.heic").is_none());
+
+        let mut svg = Vec::new();
+        render(&qr, &registry, "svg", &options, &mut svg).unwrap();
+        let view_box = format!("viewBox=\"0 0 {0} {0}\"", qr.size() + 8);
+        assert!(String::from_utf8(svg).unwrap().contains(&view_box));
+
+        registry.register(Box::new(Payload));
+        assert_eq!(registry.for_path("qr.wifi").unwrap().name(), "svg");
+
+        let mut svg = Vec::new();
+        render(&qr, &registry, "svg", &options, &mut svg).unwrap();
+        assert_eq!(svg, qr.size().to_string().as_bytes());
+
+        assert!(render(&qr, &registry, "heic", &options, &mut Vec::new()).is_err());
+
+        // trait objects work as writers too
+        let mut buffer = Vec::new();
+        let out: &mut dyn std::io::Write = &mut buffer;
+        render(&qr, &registry, "svg", &options, out).unwrap();
+        assert_eq!(buffer, qr.size().to_string().as_bytes());
+    }
+
+    /// styled svgs draw finder patterns separately, and refuse low-contrast colors
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_styled_svg() {
+        let credentials = Credentials::new(Some("test"), Some("WPA"), Some("test"), false, false);
+        let qr = encode(&credentials).unwrap();
+
+        let style = SvgStyle {
+            module_shape: ModuleShape::Dot,
+            finder_shape: FinderShape::Circle,
+            gradient: Some(Gradient::Radial {
+                to: "#003366".parse().unwrap(),
+            }),
+            finder_color: Some("#990000".parse().unwrap()),
+            ..SvgStyle::default()
+        };
+
+        let svg = make_styled_svg(&qr, Color::BLACK, Color::WHITE, &style).unwrap();
+        assert!(svg.contains("<radialGradient id=\"wifiqr-fill\""));
+        assert!(svg.contains("fill=\"url(#wifiqr-fill)\""));
+        assert!(svg.contains("fill-rule=\"evenodd\" fill=\"#990000\""));
+        // timing patterns stay square even when data modules are dots
+        assert!(svg.contains(&format!("M{},{}h1v1h-1z", 4 + 8, 4 + 6)));
+
+        let washed_out = SvgStyle {
+            finder_color: Some("#DDDDDD".parse().unwrap()),
+            ..SvgStyle::default()
+        };
+        assert!(make_styled_svg(&qr, Color::BLACK, Color::WHITE, &washed_out).is_err());
+        assert!(make_styled_svg(&qr, Color::WHITE, Color::BLACK, &SvgStyle::default()).is_err());
+    }
+
+    /// fills the module grid from the crispEdges path of an svg, using the nonzero fill rule
+    #[cfg(feature = "svg")]
+    fn svg_modules(svg: &str, size: i32, border: i32) -> Vec<bool> {
+        let start = svg.find("<path d=\"").unwrap() + 9;
+        let data = &svg[start..start + svg[start..].find('"').unwrap()];
+
+        // vertical edges as (x, top, bottom, winding)
+        let mut edges = Vec::new();
+        let (mut x, mut y, mut start_y) = (0, 0, 0);
+        let mut command = ' ';
+        let mut number = String::new();
+        for c in data.chars().chain(std::iter::once(' ')) {
+            if c.is_ascii_digit() || c == '-' || c == ',' {
+                number.push(c);
+                continue;
+            }
+            match command {
+                'M' => {
+                    let mut xy = number.split(',').map(|n| n.parse::<i32>().unwrap());
+                    x = xy.next().unwrap();
+                    y = xy.next().unwrap();
+                    start_y = y;
+                }
+                'h' => x += number.parse::<i32>().unwrap(),
+                'v' | 'z' => {
+                    let to = if command == 'v' {
+                        y + number.parse::<i32>().unwrap()
+                    } else {
+                        start_y
+                    };
+                    if to != y {
+                        edges.push((x, y.min(to), y.max(to), if to > y { 1 } else { -1 }));
                     }
+                    y = to;
                 }
+                _ => {}
             }
+            number.clear();
+            if !c.is_whitespace() {
+                command = c;
+            }
+        }
 
-            if self.hidden {
-                return Ok(format!(
-                    wifi_auth!(hidden),
-                    self.filter_credentials(&self.filter_encr(&self.encr)),
-                    self.filter_credentials(&self.ssid),
-                    self.filter_credentials(&self.pass),
-                    &self.hidden,
-                ));
-            } else {
-                return Ok(format!(
-                    wifi_auth!(),
-                    self.filter_credentials(&self.filter_encr(&self.encr)),
-                    self.filter_credentials(&self.ssid),
-                    self.filter_credentials(&self.pass)
-                ));
+        let mut modules = Vec::new();
+        for my in 0..size {
+            for mx in 0..size {
+                let (px, py) = (mx + border, my + border);
+                let winding: i32 = edges
+                    .iter()
+                    .filter(|&&(ex, top, bottom, _)| ex > px && top <= py && py < bottom)
+                    .map(|e| e.3)
+                    .sum();
+                modules.push(winding != 0);
             }
         }
+        modules
     }
 
-    /// returns a new Credentials struct given Wifi credentials. This data is not validated,
-    /// nor formatted into a QR code string. Call .format() on Credentials to do this.
-    pub fn auth(
-        _ssid: Option<&str>,
-        _password: Option<&str>,
-        _encr: Option<&str>,
-        _hidden: bool,
-        _quote: bool,
-    ) -> Credentials {
-        self::Credentials::new(_ssid, _password, _encr, _hidden, _quote)
-    }
+    /// every svg path mode draws exactly the dark modules, and optimized paths are smaller
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_svg_options() {
+        let text = "WIFI:T:WPA;S:svg;P:optimized paths;;".repeat(8);
+        let qr = QrMatrix::encode_text(&text, ErrorCorrection::High).unwrap();
+        let expected: Vec<bool> = (0..qr.size())
+            .flat_map(|y| (0..qr.size()).map(move |x| (x, y)))
+            .map(|(x, y)| qr.get_module(x, y))
+            .collect();
+
+        let mut lengths = Vec::new();
+        for &paths in [PathMode::Modules, PathMode::Runs, PathMode::Outlines].iter() {
+            let options = SvgOptions {
+                border: 2,
+                paths,
+                ..SvgOptions::default()
+            };
+            let svg = make_svg_with(&qr, &options).unwrap();
+            assert!(svg.contains(&format!("viewBox=\"0 0 {0} {0}\"", qr.size() + 4)));
+            assert!(svg.contains("shape-rendering=\"crispEdges\""));
+            assert_eq!(svg_modules(&svg, qr.size(), 2), expected);
+            lengths.push(svg.len());
+        }
+        assert!(lengths[2] < lengths[1] && lengths[1] < lengths[0]);
 
-    /// generates a qrcode from a Credentials configuration
-    pub fn encode(config: &Credentials) -> Result<QrCode, Box<dyn error::Error>> {
-        let c = match config.format() {
-            Ok(c) => c,
-            Err(e) => return Err(e.into()),
+        let options = SvgOptions {
+            width: Some(Length {
+                value: 40.0,
+                unit: Unit::Mm,
+            }),
+            ..SvgOptions::default()
         };
+        assert!(make_svg_with(&qr, &options)
+            .unwrap()
+            .contains("width=\"40mm\" height=\"40mm\""));
 
-        match QrCode::encode_text(&c, QrCodeEcc::High) {
-            Ok(qr) => Ok(qr),
-            Err(e) => Err(e.into()),
-        }
+        assert_eq!(
+            "1.5 in".parse::<Length>().unwrap(),
+            Length {
+                value: 1.5,
+                unit: Unit::In
+            }
+        );
+        assert!("40furlongs".parse::<Length>().is_err());
+        assert!("-3mm".parse::<Length>().is_err());
     }
 
-    /// manual_encode isn't intended for use externally, but exists to compare between the
-    /// automated encoder and this manual_encode version
-    /// https://docs.rs/qrcodegen/latest/src/qrcodegen/lib.rs.html#151
-    pub fn manual_encode(
-        config: &Credentials,
-        error_level: QrCodeEcc,
-        lowest_version: qrcodegen::Version,
-        highest_version: qrcodegen::Version,
-        mask_level: Option<Mask>,
-    ) -> QrCode {
-        let segs: Vec<QrSegment> = QrSegment::make_segments(&config.format().unwrap());
-
-        QrCode::encode_segments_advanced(
-            &segs,
-            error_level,
-            lowest_version,
-            highest_version,
-            mask_level,
-            true,
-        )
-        .unwrap()
+    /// labelled svgs carry a title, description and aria label, without the password by default
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_accessible_svg() {
+        let config = Credentials::new(
+            Some("Guest & Co"),
+            Some("hunter22"),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        let qr = encode(&config).unwrap();
+
+        assert_eq!(config.accessible_name(), "Wi-Fi network Guest & Co, WPA2");
+
+        let svg = make_svg_with(&qr, &SvgOptions::default().with_network(&config, false)).unwrap();
+        assert!(svg.contains("role=\"img\" aria-label=\"Wi-Fi network Guest &amp; Co, WPA2\""));
+        assert!(svg.contains("<title>Wi-Fi network Guest &amp; Co, WPA2</title>"));
+        assert!(svg.contains("<desc>"));
+        assert!(!svg.contains("hunter22"));
+
+        let svg = make_svg_with(&qr, &SvgOptions::default().with_network(&config, true)).unwrap();
+        assert!(svg.contains("The password is hunter22."));
+
+        assert!(!make_svg_with(&qr, &SvgOptions::default())
+            .unwrap()
+            .contains("<title>"));
     }
 
-    /// generates a wifi qr code that is printed to a terminal/console for quick scanning
-    /// parameters:
-    /// - qrcode: encoded qrcode
-    /// - quiet_zone: the border size to apply to the QR code (created with ASCII_BL_BLOCK)
-    /// result:
-    /// - this prints a block of text directly to the console
-    pub fn console_qr(qrcode: &QrCode, quiet_zone: i32) {
-        const ASCII_BL_BLOCK: &str = "  ";
-        const ASCII_W_BLOCK: &str = "██";
-
-        let x_zone = quiet_zone;
-        let y_zone = quiet_zone;
-
-        // paint top border -- y axis
-        for _top_border in 0..y_zone {
-            print!("{}", ASCII_BL_BLOCK);
-            println!();
-        }
+    /// html pages inline the svg, escape the network name and only show the password when asked
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_html_page() {
+        let config = Credentials::new(
+            Some("<Lobby>"),
+            Some("hunter22"),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        let qr = encode(&config).unwrap();
 
-        for y in 0..qrcode.size() {
-            // paint left border -- x axis
-            for _left_border in 0..x_zone {
-                print!("{}", ASCII_BL_BLOCK);
-            }
+        let html = make_html(&qr, &config, &HtmlOptions::default()).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Wi-Fi: &lt;Lobby&gt;</title>"));
+        assert!(html.contains("<svg xmlns="));
+        assert!(!html.contains("<?xml"));
+        assert!(html.contains("@media print"));
+        assert!(!html.contains("hunter22"));
+        assert!(!html.contains("copyPassword"));
 
-            // paint qr
-            for x in 0..qrcode.size() {
-                if qrcode.get_module(x, y) {
-                    print!("{}", ASCII_W_BLOCK);
-                } else {
-                    print!("{}", ASCII_BL_BLOCK);
-                }
-            }
+        let options = HtmlOptions {
+            include_password: true,
+            ..HtmlOptions::default()
+        };
+        let html = make_html(&qr, &config, &options).unwrap();
+        assert!(html.contains("<code id=\"password\">hunter22</code>"));
+        assert!(html.contains("onclick=\"copyPassword(this)\""));
+    }
 
-            // paint right border -- x axis
-            for _right_border in 0..x_zone {
-                print!("{}", ASCII_BL_BLOCK);
-            }
+    /// data uris carry the mime type and the base64 encoded output of the matching renderer
+    #[test]
+    fn test_data_uris() {
+        let qr =
+            QrMatrix::encode_text("WIFI:T:WPA;S:uri;P:embedded;;", ErrorCorrection::High).unwrap();
 
-            println!();
+        #[cfg(feature = "svg")]
+        {
+            let uri = make_svg_data_uri(&qr);
+            assert!(uri.starts_with("data:image/svg+xml;base64,"));
+            let decoded = base64::decode(&uri["data:image/svg+xml;base64,".len()..]).unwrap();
+            assert_eq!(String::from_utf8(decoded).unwrap(), make_svg(&qr));
         }
 
-        // paint bottom border -- y axis
-        for _bottom_border in 0..y_zone {
-            print!("{}", ASCII_BL_BLOCK);
-            println!();
+        let registry = Registry::default();
+        let options = RenderOptions::default();
+        #[cfg(feature = "raster")]
+        {
+            let image = make_image(&qr, 2, 4);
+            let mut png = Vec::new();
+            write_png(&image, &mut png).unwrap();
+            assert_eq!(
+                make_image_data_uri(&image).unwrap(),
+                format!("data:image/png;base64,{}", base64::encode(&png))
+            );
+
+            assert!(render_data_uri(&qr, &registry, "jpeg", &options)
+                .unwrap()
+                .starts_with("data:image/jpeg;base64,/9j/"));
+            assert!(render_data_uri(&qr, &registry, "gif", &options)
+                .unwrap()
+                .starts_with("data:image/gif;base64,R0lGOD"));
         }
+        assert!(render_data_uri(&qr, &registry, "pbm", &options)
+            .unwrap()
+            .starts_with("data:image/x-portable-bitmap;base64,"));
+        assert!(render_data_uri(&qr, &registry, "heic", &options).is_err());
     }
 
-    pub fn make_image(
-        qrcode: &QrCode,
-        scale: i32,
-        border_size: i32,
-    ) -> ImageBuffer<LumaA<u8>, Vec<u8>> {
-        make_image_export(qrcode, scale, border_size)
-    }
+    /// physical sizes pick the largest module size that fits, and the dpi is written into metadata
+    #[cfg(feature = "raster")]
+    #[test]
+    fn test_physical_size() {
+        let qr =
+            QrMatrix::encode_text("WIFI:T:WPA;S:print;P:300 dpi;;", ErrorCorrection::High).unwrap();
+        let modules = (qr.size() + 8) as u32;
 
-    /// generates an svg string from a QrCode (output from the QR library)
-    ///
-    /// * qrcode: &QrCode
-    ///
-    pub fn make_svg(qrcode: &QrCode) -> String {
-        to_svg_string_export(qrcode, 4)
+        // 40mm at 300 DPI is 472 pixels
+        let options = RenderOptions {
+            size: "40mm".parse().ok(),
+            dpi: Some(300),
+            ..RenderOptions::default()
+        };
+        let layout = options.raster_layout(&qr).unwrap();
+        assert_eq!(layout.size, 472);
+        assert_eq!(layout.module_size, 472 / modules);
+        assert_eq!(
+            layout.offset,
+            4 * layout.module_size + (472 - layout.module_size * modules) / 2
+        );
+        assert!(options.warnings(&qr).is_empty());
+
+        let image = make_image_with(&qr, &options).unwrap();
+        assert_eq!(image.dimensions(), (472, 472));
+        assert_eq!(image.get_pixel(layout.offset, layout.offset)[0], 0);
+        assert_eq!(image.get_pixel(layout.offset - 1, layout.offset)[0], 255);
+
+        // pHYs holds 11811 pixels per meter (300 DPI) on both axes
+        let registry = Registry::default();
+        let mut png = Vec::new();
+        render(&qr, &registry, "png", &options, &mut png).unwrap();
+        let phys = png.windows(4).position(|w| w == b"pHYs").unwrap();
+        assert_eq!(
+            &png[phys + 4..phys + 13],
+            &[0, 0, 0x2e, 0x23, 0, 0, 0x2e, 0x23, 1][..]
+        );
+        assert!(phys < png.windows(4).position(|w| w == b"IDAT").unwrap());
+
+        // JFIF: density unit 1 (dots per inch), then the horizontal density
+        let mut jpeg = Vec::new();
+        render(&qr, &registry, "jpeg", &options, &mut jpeg).unwrap();
+        assert_eq!(&jpeg[6..11], b"JFIF\0");
+        assert_eq!(&jpeg[13..16], &[1, 0x01, 0x2c][..]);
+
+        let exact = RenderOptions {
+            size: "512px".parse().ok(),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            make_image_with(&qr, &exact).unwrap().dimensions(),
+            (512, 512)
+        );
+
+        let small = RenderOptions {
+            size: "10mm".parse().ok(),
+            dpi: Some(150),
+            ..RenderOptions::default()
+        };
+        assert_eq!(small.warnings(&qr).len(), 1);
+
+        let too_small = RenderOptions {
+            size: "20px".parse().ok(),
+            ..RenderOptions::default()
+        };
+        assert!(make_image_with(&qr, &too_small).is_err());
+
+        // sizes that would exhaust memory (or overflow) are refused before anything is allocated
+        let too_large = [
+            RenderOptions {
+                size: "1000cm".parse().ok(),
+                dpi: Some(1200),
+                ..RenderOptions::default()
+            },
+            RenderOptions {
+                module_size: 100_000,
+                ..RenderOptions::default()
+            },
+            RenderOptions {
+                quiet_zone: i32::MAX,
+                ..RenderOptions::default()
+            },
+        ];
+        for options in too_large.iter() {
+            assert!(options.raster_layout(&qr).is_err());
+        }
     }
 
-    /// saves an image to a file
-    ///
-    /// * image: ImageBuffer<>
-    ///
-    /// * save_file: file path to save the image into
-    pub fn save_image(
-        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
-        save_file: String,
-    ) -> Result<(), image::ImageError> {
-        save_image_export(image, save_file)
+    /// the row rasterizer matches a pixel-by-pixel lookup of every module
+    #[cfg(feature = "raster")]
+    #[test]
+    fn test_rasterizer() {
+        let qr = QrMatrix::encode_text("WIFI:T:WPA;S:raster;P:row by row;;", ErrorCorrection::High)
+            .unwrap();
+
+        let image = make_image(&qr, 3, 5);
+        let size = (qr.size() * 3 + 10) as u32;
+        assert_eq!(image.dimensions(), (size, size));
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let (mx, my) = ((x as i32 - 5).div_euclid(3), (y as i32 - 5).div_euclid(3));
+            let dark = x >= 5 && y >= 5 && qr.get_module(mx, my);
+            assert_eq!(pixel[0], if dark { 0 } else { 255 }, "pixel {},{}", x, y);
+            assert_eq!(pixel[1], 255);
+        }
+
+        // uneven leftovers from an exact size end up in the quiet zone
+        let options = RenderOptions {
+            size: "301px".parse().ok(),
+            dark_color: "#102030".parse().unwrap(),
+            ..RenderOptions::default()
+        };
+        let layout = options.raster_layout(&qr).unwrap();
+        let image = make_image_with(&qr, &options).unwrap();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected = if layout.is_dark(&qr, x, y) {
+                [0x10, 0x20, 0x30, 255]
+            } else {
+                [255, 255, 255, 255]
+            };
+            assert_eq!(pixel.0, expected, "pixel {},{}", x, y);
+        }
     }
 
-    /// this error is returned when a potentally invalid combination of choices are made in the process
-    /// of building a wifi connection string to embed as a QR code.
-    ///
-    /// a recommendation is returned to the caller as a string to provide corrective action
-    #[derive(Debug, Clone)]
-    pub struct FormatError(String);
+    /// two-color pngs are written with 1-bit samples, as gray levels or palette indexes
+    #[cfg(feature = "raster")]
+    #[test]
+    fn test_png_modes() {
+        let qr =
+            QrMatrix::encode_text("WIFI:T:WPA;S:png;P:one bit;;", ErrorCorrection::High).unwrap();
+        let registry = Registry::default();
+        // IHDR: bit depth and color type follow the 8 byte signature, chunk header and dimensions
+        let depth_and_color = |png: &[u8]| (png[24], png[25]);
+
+        let png_with = |png_mode, transparent, dark_color| {
+            let options = RenderOptions {
+                png_mode,
+                transparent,
+                dark_color,
+                ..RenderOptions::default()
+            };
+            let mut png = Vec::new();
+            render(&qr, &registry, "png", &options, &mut png).map(|_| png)
+        };
+
+        let rgba = png_with(PngMode::Rgba, false, Color::BLACK).unwrap();
+        let gray = png_with(PngMode::Gray, false, Color::BLACK).unwrap();
+        let palette = png_with(PngMode::Palette, true, Color::BLACK).unwrap();
+        assert_eq!(depth_and_color(&rgba), (8, 6));
+        assert_eq!(depth_and_color(&gray), (1, 0));
+        assert_eq!(depth_and_color(&palette), (1, 3));
+        assert!(gray.len() * 2 < rgba.len());
+        assert!(palette.windows(4).any(|w| w == b"tRNS"));
+        assert!(!gray.windows(4).any(|w| w == b"tRNS"));
+
+        let decoded = |png: &[u8]| image::load_from_memory(png).unwrap().to_rgba8();
+        let expected = decoded(&rgba);
+        assert_eq!(decoded(&gray), expected);
+        let transparent = decoded(&palette);
+        for (t, e) in transparent.pixels().zip(expected.pixels()) {
+            assert_eq!(t[3] == 0, e[0] == 255);
+        }
+
+        let blue: Color = "#1A2B8C".parse().unwrap();
+        assert!(png_with(PngMode::Gray, false, blue).is_err());
+        let colored = decoded(&png_with(PngMode::Palette, false, blue).unwrap());
+        assert_eq!(
+            colored,
+            decoded(&png_with(PngMode::Rgba, false, blue).unwrap())
+        );
 
-    impl std::error::Error for FormatError {
-        fn description(&self) -> &str {
-            &self.0
+        let image = make_image(&qr, 4, 16);
+        let mut one_bit = Vec::new();
+        write_png_1bit(&image, false, &mut one_bit).unwrap();
+        assert_eq!(depth_and_color(&one_bit), (1, 0));
+        let luma = image::load_from_memory(&one_bit).unwrap().to_luma8();
+        for (a, b) in luma.pixels().zip(image.pixels()) {
+            assert_eq!(a[0], b[0]);
         }
     }
 
-    impl std::fmt::Display for FormatError {
-        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            f.write_str(&self.0)
+    /// every raster format decodes back to the same pixels as png output
+    #[cfg(feature = "raster")]
+    #[test]
+    fn test_raster_formats() {
+        let qr = QrMatrix::encode_text(
+            "WIFI:T:WPA;S:formats;P:bmp gif webp;;",
+            ErrorCorrection::Medium,
+        )
+        .unwrap();
+        let registry = Registry::default();
+        let options = RenderOptions {
+            module_size: 4,
+            ..RenderOptions::default()
+        };
+        let rendered = |format: &str, options: &RenderOptions| {
+            let mut out = Vec::new();
+            render(&qr, &registry, format, options, &mut out).map(|_| out)
+        };
+        let expected = image::load_from_memory(&rendered("png", &options).unwrap())
+            .unwrap()
+            .to_rgb8();
+
+        for (format, magic) in &[
+            ("bmp", &b"BM"[..]),
+            ("gif", b"GIF89a"),
+            ("webp", b"RIFF"),
+            ("tiff", b"II*\0"),
+            ("ico", b"\0\0\x01\0"),
+            ("pbm", b"P4\n"),
+            ("pnm", b"P6\n"),
+        ] {
+            let data = rendered(format, &options).unwrap();
+            assert!(data.starts_with(magic), "{}", format);
+            let decoded = image::load_from_memory(&data).unwrap().to_rgb8();
+            assert_eq!(decoded, expected, "{}", format);
         }
+
+        // ICO is limited to 256 pixels, and larger codes are scaled down unless a size is given
+        let large = RenderOptions {
+            module_size: 20,
+            ..RenderOptions::default()
+        };
+        let icon = image::load_from_memory(&rendered("ico", &large).unwrap()).unwrap();
+        assert!(icon.width() <= 256 && icon.width() > 200);
+        let sized = RenderOptions {
+            size: "300px".parse().ok(),
+            ..large
+        };
+        assert!(rendered("ico", &sized).is_err());
+
+        let blue = RenderOptions {
+            dark_color: "#1A2B8C".parse().unwrap(),
+            ..options
+        };
+        assert!(rendered("pbm", &blue).is_err());
+        assert!(rendered("pnm", &blue).is_ok());
     }
 
-}
\ No newline at end of file
+    /// the module grid is exported with the quiet zone, and every format agrees with get_module()
+    #[test]
+    fn test_module_matrix() {
+        let qr =
+            QrMatrix::encode_text("WIFI:T:WPA;S:matrix;P:led panel;;", ErrorCorrection::Medium)
+                .unwrap();
+        let size = qr.size() as usize;
+
+        let matrix = module_matrix(&qr, 2);
+        assert_eq!(matrix.len(), size + 4);
+        assert!(matrix.iter().all(|row| row.len() == size + 4));
+        assert!(matrix[0].iter().all(|&dark| !dark));
+        for y in 0..qr.size() {
+            for x in 0..qr.size() {
+                assert_eq!(matrix[y as usize + 2][x as usize + 2], qr.get_module(x, y));
+            }
+        }
+        assert_eq!(module_matrix(&qr, 0).len(), size);
+
+        let written = |format: &str| {
+            let mut out = Vec::new();
+            write_matrix(&qr, 2, format.parse::<MatrixFormat>().unwrap(), &mut out).unwrap();
+            out
+        };
+
+        let json: Vec<Vec<u8>> = serde_json::from_slice(&written("json")).unwrap();
+        let text = String::from_utf8(written("text")).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), size + 4);
+        for (y, row) in matrix.iter().enumerate() {
+            let bits: Vec<u8> = row.iter().map(|&dark| dark as u8).collect();
+            assert_eq!(json[y], bits);
+            let chars: String = bits.iter().map(|b| b.to_string()).collect();
+            assert_eq!(lines[y], chars);
+        }
+
+        #[cfg(feature = "raster")]
+        {
+            let pbm = image::load_from_memory(&written("pbm")).unwrap().to_luma8();
+            assert_eq!(pbm.width() as usize, size + 4);
+            for (x, y, pixel) in pbm.enumerate_pixels() {
+                assert_eq!(pixel[0] == 0, matrix[y as usize][x as usize]);
+            }
+        }
+
+        assert!("png".parse::<MatrixFormat>().is_err());
+    }
+
+    /// emoji and CJK SSIDs encode to the same code every time, and utf8_ssid marks them with ECI 26
+    #[test]
+    fn test_unicode_ssids() {
+        let mut goldens = Vec::new();
+
+        for ssid in ["東京オフィス", "☕ Café 🐱"].iter() {
+            let mut config = Credentials::new(
+                Some(ssid),
+                Some("パスワード123"),
+                Some("wpa2"),
+                false,
+                false,
+            );
+            let payload = config.format().unwrap();
+            assert_eq!(payload, format!("WIFI:T:WPA2;S:{};P:パスワード123;;", ssid));
+
+            let plain = encode(&config).unwrap();
+            assert_eq!(
+                plain,
+                QrMatrix::encode_text(&payload, ErrorCorrection::High).unwrap()
+            );
+
+            config.utf8_ssid = true;
+            let marked = encode(&config).unwrap();
+            assert_eq!(
+                marked,
+                QrMatrix::encode_utf8(&payload, ErrorCorrection::High).unwrap()
+            );
+            assert_ne!(marked, plain);
+            assert_eq!(marked, encode(&config.clone()).unwrap());
+
+            goldens.push((
+                marked.version(),
+                marked.mask(),
+                marked.dark_modules().count(),
+            ));
+        }
+        // pinned, so that a change in segmenting or masking shows up here
+        assert_eq!(goldens, vec![(6, 1, 868), (6, 1, 852)]);
+
+        // plain ASCII payloads never carry the designator
+        let mut ascii =
+            Credentials::new(Some("Tokyo"), Some("password"), Some("wpa2"), false, false);
+        ascii.utf8_ssid = true;
+        let payload = ascii.format().unwrap();
+        assert_eq!(
+            encode(&ascii).unwrap(),
+            QrMatrix::encode_text(&payload, ErrorCorrection::High).unwrap()
+        );
+
+        // SSIDs are limited to 32 octets, not characters: ten CJK characters fit, eleven don't
+        let ten = Credentials::new(
+            Some(&"東".repeat(10)),
+            Some(""),
+            Some("nopass"),
+            false,
+            false,
+        );
+        assert!(ten.format().is_ok());
+        let eleven = Credentials::new(
+            Some(&"東".repeat(11)),
+            Some(""),
+            Some("nopass"),
+            false,
+            false,
+        );
+        assert!(eleven
+            .format()
+            .unwrap_err()
+            .to_string()
+            .contains("33 bytes"));
+    }
+
+    /// SSIDs that are not UTF-8 are written in hex, and survive ONC and JSON round trips
+    #[test]
+    fn test_raw_ssid() {
+        // "テスト" in Shift JIS
+        let bytes = [0x83, 0x65, 0x83, 0x58, 0x83, 0x67];
+        let config = Credentials::new(Some(""), Some("password"), Some("wpa2"), false, false)
+            .with_ssid_bytes(&bytes);
+
+        assert_eq!(config.raw_ssid, Some(bytes.to_vec()));
+        assert_eq!(config.ssid_bytes(), &bytes[..]);
+        assert_eq!(
+            config.format().unwrap(),
+            "WIFI:T:WPA2;S:836583588367;P:password;;"
+        );
+        assert!(encode(&config).is_ok());
+
+        // --ssid-hex decodes with the same function as serialized ssid_hex values
+        assert_eq!(from_hex("836583588367"), Some(bytes.to_vec()));
+        assert_eq!(from_hex("cafe"), Some(vec![0xca, 0xfe]));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+        // from_str_radix() alone would read these as 0x0F and 0x0C
+        assert_eq!(from_hex("+f"), None);
+        assert_eq!(from_hex("+f+c"), None);
+
+        // valid UTF-8 is just a name
+        let utf8 = config.clone().with_ssid_bytes("東京".as_bytes());
+        assert_eq!(utf8.raw_ssid, None);
+        assert_eq!(utf8.ssid, "東京");
+
+        let onc = to_onc(&config).unwrap();
+        assert!(onc.contains(r#""HexSSID": "836583588367""#));
+        let imported = from_onc(&onc).unwrap();
+        assert_eq!(imported[0].raw_ssid, Some(bytes.to_vec()));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_value(&config).unwrap();
+            assert_eq!(json["ssid_hex"], "836583588367");
+            assert!(json.get("utf8_ssid").is_none());
+            let parsed: Credentials = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed, config);
+        }
+
+        let long = config.with_ssid_bytes(&[0xff; 33]);
+        assert!(long.format().is_err());
+    }
+
+    /// QrMatrix reports the version, error correction and mask it was encoded with, and its dark
+    /// modules agree with get_module()
+    #[test]
+    fn test_qr_matrix() {
+        let credentials = Credentials::new(
+            Some("matrix"),
+            Some("owned type"),
+            Some("WPA"),
+            false,
+            false,
+        );
+
+        let qr = manual_encode(&credentials, ErrorCorrection::High, 5, 40, Some(3)).unwrap();
+        assert_eq!(qr.version(), 5);
+        assert_eq!(qr.size(), 4 * 5 + 17);
+        assert_eq!(qr.error_correction(), ErrorCorrection::High);
+        assert_eq!(qr.mask(), 3);
+
+        let dark: Vec<(i32, i32)> = qr.dark_modules().collect();
+        let expected: Vec<(i32, i32)> = (0..qr.size())
+            .flat_map(|y| (0..qr.size()).map(move |x| (x, y)))
+            .filter(|&(x, y)| qr.get_module(x, y))
+            .collect();
+        assert_eq!(dark, expected);
+        // the top left finder pattern starts with a dark module
+        assert_eq!(dark[0], (0, 0));
+        assert!(!qr.get_module(-1, 0));
+        assert!(!qr.get_module(0, qr.size()));
+
+        let auto = encode(&credentials).unwrap();
+        assert_eq!(auto.error_correction(), ErrorCorrection::High);
+        assert_eq!(auto.size(), 4 * auto.version() as i32 + 17);
+        assert!(format!("{:?}", auto).contains("error_correction: High"));
+
+        // out-of-range versions and masks are errors, not panics
+        assert!(manual_encode(&credentials, ErrorCorrection::High, 0, 40, None).is_err());
+        assert!(manual_encode(&credentials, ErrorCorrection::High, 1, 41, None).is_err());
+        assert!(manual_encode(&credentials, ErrorCorrection::High, 10, 5, None).is_err());
+        assert!(manual_encode(&credentials, ErrorCorrection::High, 1, 40, Some(8)).is_err());
+        assert!(manual_encode(&credentials, ErrorCorrection::High, 1, 1, None).is_err());
+        let unformattable = Credentials::new(Some("matrix"), Some(""), Some("WPA"), false, false);
+        assert!(manual_encode(&unformattable, ErrorCorrection::High, 1, 40, None).is_err());
+
+        assert_eq!(
+            "q".parse::<ErrorCorrection>().unwrap(),
+            ErrorCorrection::Quartile
+        );
+        assert_eq!(
+            "Medium".parse::<ErrorCorrection>().unwrap(),
+            ErrorCorrection::Medium
+        );
+        assert!("extreme".parse::<ErrorCorrection>().is_err());
+    }
+
+    /// C headers and XBM images hold the same bits as the rasterized code, in the requested order
+    #[test]
+    fn test_bitmaps() {
+        let qr =
+            QrMatrix::encode_text("WIFI:T:WPA;S:badge;P:e-ink;;", ErrorCorrection::Low).unwrap();
+        let registry = Registry::default();
+        let options = RenderOptions {
+            module_size: 3,
+            quiet_zone: 1,
+            bitmap_name: Some("guest-wifi 2".to_string()),
+            ..RenderOptions::default()
+        };
+        let size = (qr.size() + 2) as u32 * 3;
+        let stride = size.div_ceil(8) as usize;
+        let dark = |x: u32, y: u32| qr.get_module(x as i32 / 3 - 1, y as i32 / 3 - 1);
+        // bit j of row (or column) i, most significant bit first
+        let bit = |bytes: &[u8], i: usize, j: u32| {
+            bytes[i * stride + j as usize / 8] >> (7 - j % 8) & 1 == 1
+        };
+
+        // the array from the body of a C initializer
+        let array = |source: &str| -> Vec<u8> {
+            let body = &source[source.find('{').unwrap() + 1..source.rfind('}').unwrap()];
+            body.split(',')
+                .map(|b| u8::from_str_radix(b.trim().trim_start_matches("0x"), 16).unwrap())
+                .collect()
+        };
+        let rendered = |format: &str, options: &RenderOptions| {
+            let mut out = Vec::new();
+            render(&qr, &registry, format, options, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let header = rendered("c", &options);
+        assert!(header.contains(&format!("#define GUEST_WIFI_2_WIDTH {}", size)));
+        assert!(header.contains(&format!("#define GUEST_WIFI_2_HEIGHT {}", size)));
+        let declaration = format!(
+            "const uint8_t guest_wifi_2[{}] = {{",
+            stride * size as usize
+        );
+        assert!(header.contains(&declaration));
+        let rows = array(&header);
+        assert_eq!((size, rows.clone()), pack_bitmap(&qr, &options).unwrap());
+
+        let columns = RenderOptions {
+            bitmap_layout: "columns".parse().unwrap(),
+            ..options.clone()
+        };
+        assert_eq!(columns.bitmap_layout, BitmapLayout::Columns);
+        let columns = array(&rendered("c", &columns));
+        let inverted = RenderOptions {
+            invert: true,
+            ..options.clone()
+        };
+        let inverted = array(&rendered("c", &inverted));
+
+        for y in 0..size {
+            for x in 0..size {
+                assert_eq!(bit(&rows, y as usize, x), dark(x, y));
+                assert_eq!(bit(&columns, x as usize, y), dark(x, y));
+                assert_eq!(bit(&inverted, y as usize, x), !dark(x, y));
+            }
+        }
+
+        // XBM puts the leftmost pixel in the least significant bit
+        let xbm = rendered("xbm", &options);
+        assert!(xbm.starts_with(&format!("#define guest_wifi_2_width {}\n", size)));
+        assert!(xbm.contains("static unsigned char guest_wifi_2_bits[] = {"));
+        let xbm: Vec<u8> = array(&xbm).iter().map(|b| b.reverse_bits()).collect();
+        assert_eq!(xbm, rows);
+    }
+
+    /// the no_std payload module formats the same string that encode() puts into the code
+    #[test]
+    fn test_payload() {
+        let config = super::payload::auth(Some("lobby"), Some("p;ss"), Some("wpa2"), false, false);
+        let payload = config.format().unwrap();
+        assert_eq!(payload, "WIFI:T:WPA2;S:lobby;P:p\\;ss;;");
+
+        let expected = QrMatrix::encode_text(&payload, ErrorCorrection::High).unwrap();
+        assert!(encode(&config).unwrap() == expected);
+
+        let error: super::payload::FormatError =
+            super::payload::auth(Some("lobby"), Some(""), Some("wpa2"), false, false)
+                .format()
+                .unwrap_err();
+        assert!(error.to_string().contains("requires a password"));
+    }
+
+    /// credentials round-trip through the versioned JSON schema, and redaction leaves out the password
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_schema() {
+        let config = Credentials::new(Some("Guest"), Some("p;ss"), Some("wpa2"), true, false);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": 1,
+                "ssid": "Guest",
+                "password": "p;ss",
+                "encryption": "wpa2",
+                "hidden": true,
+                "quote": false
+            })
+        );
+        let parsed: Credentials = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, config);
+
+        let redacted = serde_json::to_string(&config.redacted()).unwrap();
+        assert!(!redacted.contains("password") && !redacted.contains("p;ss"));
+        let parsed: Credentials = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(parsed.pass, "");
+        assert_eq!(parsed.ssid, "Guest");
+
+        // optional fields default, and other versions are rejected
+        let open: Credentials =
+            serde_json::from_str(r#"{"version":1,"ssid":"Cafe","encryption":"nopass"}"#).unwrap();
+        assert_eq!(open.format().unwrap(), "WIFI:T:nopass;S:Cafe;;");
+        let error = serde_json::from_str::<Credentials>(
+            r#"{"version":2,"ssid":"Cafe","encryption":"nopass"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("version 2"));
+        let unversioned = r#"{"ssid":"Cafe","encryption":"nopass"}"#;
+        assert!(serde_json::from_str::<Credentials>(unversioned).is_err());
+
+        assert_eq!(
+            serde_json::to_string(&PngMode::Palette).unwrap(),
+            "\"palette\""
+        );
+        let layout: BitmapLayout = serde_json::from_str("\"columns\"").unwrap();
+        assert_eq!(layout, BitmapLayout::Columns);
+    }
+
+    /// each profile decides casing, quoting, escaping, H:false and field order
+    #[test]
+    fn test_profiles() {
+        let formatted = |profile: &str, hidden: bool, quote: bool| {
+            let mut config = Credentials::new(
+                Some("Café:Bar,1"),
+                Some("DEADBEEF"),
+                Some("wpa2"),
+                hidden,
+                quote,
+            );
+            config.profile = profile.parse().unwrap();
+            config.format().unwrap()
+        };
+
+        assert_eq!(
+            formatted("max-compat", false, false),
+            "WIFI:T:WPA2;S:Café:Bar,1;P:DEADBEEF;;"
+        );
+        assert_eq!(
+            formatted("max-compat", false, true),
+            r#"WIFI:T:WPA2;S:"Café:Bar,1";P:"DEADBEEF";;"#
+        );
+        assert_eq!(
+            formatted("ios", false, true),
+            "WIFI:T:WPA2;S:Café:Bar,1;P:DEADBEEF;;"
+        );
+        assert_eq!(
+            formatted("android", false, false),
+            r#"WIFI:S:Café\:Bar\,1;T:wpa2;P:"DEADBEEF";H:false;;"#
+        );
+        assert_eq!(
+            formatted("android", true, false),
+            r#"WIFI:S:Café\:Bar\,1;T:wpa2;P:"DEADBEEF";H:true;;"#
+        );
+        assert_eq!(
+            formatted("zxing", true, false),
+            r#"WIFI:T:WPA2;S:Café\:Bar\,1;P:"DEADBEEF";H:true;;"#
+        );
+        assert_eq!(
+            formatted("strict", false, false),
+            r#"WIFI:T:WPA;S:Café\:Bar\,1;P:"DEADBEEF";;"#
+        );
+
+        // the default is max-compat, which is the format written before profiles existed
+        assert_eq!(
+            Credentials::new(Some("a"), Some(""), Some("nopass"), false, false).profile,
+            Profile::MaxCompat
+        );
+
+        // strict only allows the encryption types from the format description
+        let mut sae = Credentials::new(Some("Office"), Some("password"), Some("sae"), false, false);
+        assert_eq!(sae.format().unwrap(), "WIFI:T:SAE;S:Office;P:password;;");
+        sae.profile = Profile::Strict;
+        assert!(sae
+            .format()
+            .unwrap_err()
+            .to_string()
+            .contains("strict profile"));
+        // enterprise networks need EAP settings the payload can't carry
+        let mut corp =
+            Credentials::new(Some("corp"), Some("secret"), Some("wpa2-eap"), false, false);
+        corp.profile = Profile::Strict;
+        assert!(corp.format().is_err());
+
+        // the android profile writes the type as given, which lint only reports for bare payloads
+        let mut android =
+            Credentials::new(Some("Home"), Some("password"), Some("wpa2"), false, false);
+        android.profile = Profile::Android;
+        let payload = android.format().unwrap();
+        assert_eq!(payload, "WIFI:S:Home;T:wpa2;P:password;H:false;;");
+        assert!(lint(&android).is_empty());
+        assert_eq!(
+            lint_payload(&payload)[0].kind,
+            IssueKind::LowercaseEncryption
+        );
+
+        // raw SSIDs are meant to be read as hex, so they are never quoted
+        let mut raw = Credentials::new(Some(""), Some(""), Some("nopass"), false, false)
+            .with_ssid_bytes(&[0xca, 0xfe]);
+        raw.profile = Profile::Zxing;
+        assert_eq!(raw.format().unwrap(), "WIFI:T:nopass;S:CAFE;;");
+
+        // hex keys are keys, so quoting them would turn them into passphrases
+        let mut wep = Credentials::new(Some("Lab"), Some("0123456789"), Some("wep"), false, false);
+        wep.profile = Profile::Zxing;
+        assert_eq!(wep.format().unwrap(), "WIFI:T:WEP;S:Lab;P:0123456789;;");
+
+        assert_eq!(
+            "MAX-COMPAT".parse::<Profile>().unwrap().to_string(),
+            "max-compat"
+        );
+        assert!("windows".parse::<Profile>().is_err());
+        #[cfg(feature = "serde")]
+        {
+            let mut config =
+                Credentials::new(Some("Guest"), Some("password"), Some("wpa2"), false, false);
+            config.profile = Profile::Android;
+            let json = serde_json::to_value(&config).unwrap();
+            assert_eq!(json["profile"], "android");
+            assert_eq!(serde_json::from_value::<Credentials>(json).unwrap(), config);
+        }
+    }
+
+    /// lint reports compatibility issues by kind and severity, and nothing for a clean network
+    #[test]
+    fn test_lint() {
+        let kinds = |issues: Vec<Issue>| -> Vec<(Severity, IssueKind)> {
+            issues
+                .iter()
+                .map(|issue| (issue.severity, issue.kind))
+                .collect()
+        };
+
+        let clean = Credentials::new(
+            Some("Guest"),
+            Some("correct horse"),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        assert!(lint(&clean).is_empty());
+        assert!(lint_payload(&clean.format().unwrap()).is_empty());
+
+        assert_eq!(
+            kinds(lint_payload("WIFI:T:wpa;S:CAFE;P:short;;")),
+            vec![
+                (Severity::Warning, IssueKind::LowercaseEncryption),
+                (Severity::Warning, IssueKind::HexSsid),
+                (Severity::Error, IssueKind::Password),
+            ]
+        );
+        // quoting takes care of hex values. 10 hex digits are a WEP key, but a WPA passphrase
+        assert!(lint_payload(r#"WIFI:T:WEP;S:"CAFE";P:"0123456789";;"#).is_empty());
+        assert_eq!(
+            kinds(lint_payload("WIFI:T:WEP;S:x;P:0123456789;;")),
+            vec![(Severity::Warning, IssueKind::HexPassword)]
+        );
+        assert!(lint_payload("WIFI:T:WPA;S:x;P:0123456789;;").is_empty());
+        // every raw key length is a valid password, so quoting one is the only fix offered
+        let wep232 = format!("WIFI:T:WEP;S:x;P:{};;", "a".repeat(58));
+        assert_eq!(
+            kinds(lint_payload(&wep232)),
+            vec![(Severity::Warning, IssueKind::HexPassword)]
+        );
+        let wpa256 = format!("WIFI:T:WPA;S:x;P:{};;", "a".repeat(64));
+        assert_eq!(
+            kinds(lint_payload(&wpa256)),
+            vec![(Severity::Warning, IssueKind::HexPassword)]
+        );
+
+        assert_eq!(
+            kinds(lint_payload("T:WPA;S:x;P:password;;")),
+            vec![(Severity::Error, IssueKind::Prefix)]
+        );
+        assert_eq!(
+            kinds(lint_payload("WIFI:T:WPA;P:password;S:x")),
+            vec![(Severity::Error, IssueKind::Terminator)]
+        );
+        assert_eq!(
+            kinds(lint_payload("WIFI:T:WPA;S:x;P:password;")),
+            vec![(Severity::Warning, IssueKind::Terminator)]
+        );
+        assert_eq!(
+            kinds(lint_payload("WIFI:T:nopass;;")),
+            vec![(Severity::Error, IssueKind::MissingSsid)]
+        );
+
+        // escapes are undone before checking, and : and , are not worth a warning
+        assert!(lint_payload(r#"WIFI:T:WPA;S:a\;b:c,d;P:password;;"#).is_empty());
+        assert_eq!(
+            kinds(lint_payload("WIFI:T:WPA;S: Guest;P:pass\u{7}word;;")),
+            vec![
+                (Severity::Warning, IssueKind::Whitespace),
+                (Severity::Error, IssueKind::Character)
+            ]
+        );
+        // an escaped backslash before the closing quote leaves the value quoted
+        let backslash = lint_payload(r#"WIFI:S:a;T:WPA;P:"abc\\";;"#);
+        assert_eq!(
+            kinds(backslash.clone()),
+            vec![
+                (Severity::Warning, IssueKind::HexSsid),
+                (Severity::Warning, IssueKind::Character),
+                (Severity::Error, IssueKind::Password)
+            ]
+        );
+        assert!(backslash[1].message.contains("contains `\\`"));
+        assert!(backslash[2].message.contains("not 4 bytes"));
+        let escaped_quote = lint_payload(r#"WIFI:S:a;T:WPA;P:"abcdefgh\";;"#);
+        assert!(escaped_quote[1].message.contains("contains `\"`"));
+
+        // the password is named, but never shown
+        let secret = lint_payload("WIFI:T:WPA;S:x;P: hunter2 ü;;");
+        assert_eq!(
+            kinds(secret.clone()),
+            vec![
+                (Severity::Warning, IssueKind::Whitespace),
+                (Severity::Warning, IssueKind::Charset)
+            ]
+        );
+        assert!(secret
+            .iter()
+            .all(|issue| issue.message.starts_with("The password ")
+                && !issue.message.contains("hunter2")));
+        assert!(lint_payload("WIFI:T:WPA;S: Guest;P:password;;")[0]
+            .message
+            .contains("` Guest`"));
+
+        let dense = Credentials::new(
+            Some("Guest"),
+            Some(&"x".repeat(63)),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        assert_eq!(
+            kinds(lint(&dense)),
+            vec![(Severity::Warning, IssueKind::Density)]
+        );
+        // the ECI designator of utf8_ssid takes a version 9 code where plain text fits version 8
+        let mut marked = Credentials::new(
+            Some("Café Guest 東京"),
+            Some(&"p".repeat(47)),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        assert_eq!(
+            kinds(lint(&marked)),
+            vec![(Severity::Warning, IssueKind::Charset)]
+        );
+        marked.utf8_ssid = true;
+        assert_eq!(
+            kinds(lint(&marked)),
+            vec![(Severity::Warning, IssueKind::Density)]
+        );
+
+        // issues the credentials already handle are left out
+        let mut tokyo =
+            Credentials::new(Some("東京"), Some("password"), Some("wpa2"), false, false);
+        assert_eq!(
+            kinds(lint(&tokyo)),
+            vec![(Severity::Warning, IssueKind::Charset)]
+        );
+        tokyo.utf8_ssid = true;
+        assert!(lint(&tokyo).is_empty());
+        let raw = clean.clone().with_ssid_bytes(&[0xca, 0xfe, 0xff]);
+        assert!(lint(&raw).is_empty());
+
+        let unformattable = Credentials::new(Some("Guest"), Some(""), Some("wpa2"), false, false);
+        assert_eq!(
+            kinds(lint(&unformattable)),
+            vec![(Severity::Error, IssueKind::Format)]
+        );
+        assert_eq!(
+            lint(&unformattable)[0].to_string(),
+            "error: The encryption method requested requires a password."
+        );
+    }
+
+    /// pasted-in invisible characters, unusual spaces, NFD accents and look-alike letters are
+    /// reported by code point, and only removed when asked to
+    #[test]
+    fn test_suspicious_characters() {
+        let found = |value: &str| -> Vec<(Suspicion, char, usize)> {
+            find_suspicious(value)
+                .iter()
+                .map(|s| (s.suspicion, s.ch, s.position))
+                .collect()
+        };
+
+        assert!(find_suspicious("Café Guest").is_empty());
+        assert_eq!(
+            found("Guest\u{200B}\u{A0}Net"),
+            vec![
+                (Suspicion::Invisible, '\u{200B}', 6),
+                (Suspicion::Space, '\u{A0}', 7)
+            ]
+        );
+        assert_eq!(
+            found("Cafe\u{301}"),
+            vec![(Suspicion::Decomposed, '\u{301}', 5)]
+        );
+        assert_eq!(
+            found("G\u{430}st"),
+            vec![(Suspicion::Lookalike, '\u{430}', 2)]
+        );
+        // a name written in Cyrillic, and joiners inside emoji, are fine
+        assert!(find_suspicious("Кафе").is_empty());
+        // look-alikes count within a word, so a Cyrillic word next to a Latin one is fine
+        assert!(find_suspicious("WiFi Гость").is_empty());
+        assert_eq!(normalize_text("WiFi Гость"), "WiFi Гость");
+        assert_eq!(
+            found("Гость G\u{430}st"),
+            vec![(Suspicion::Lookalike, '\u{430}', 8)]
+        );
+        assert_eq!(normalize_text("Гость-G\u{430}st"), "Гость-Gast");
+        assert!(find_suspicious("👩\u{200D}💻").is_empty());
+
+        assert_eq!(normalize_text("Guest\u{200B}\u{A0}Net"), "Guest Net");
+        assert_eq!(normalize_text("Cafe\u{301}"), "Café");
+        assert_eq!(normalize_text("G\u{430}st"), "Gast");
+        // accents compose one after the other, and unknown ones are kept
+        assert_eq!(
+            normalize_text("u\u{308}\u{304}x\u{301}"),
+            "ü\u{304}x\u{301}"
+        );
+
+        let suspect = SuspiciousChar {
+            suspicion: Suspicion::Decomposed,
+            ch: '\u{301}',
+            position: 5,
+            replacement: Some('é'),
+        };
+        assert_eq!(
+            suspect.to_string(),
+            "U+0301 COMBINING ACUTE ACCENT at character 5, an accent stored apart from its letter (NFD); composed, it is `é` (U+00E9)"
+        );
+
+        let pasted = Credentials::new(
+            Some("Cafe\u{301}"),
+            Some("hunter\u{200B}22"),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        assert_eq!(
+            pasted.format().unwrap(),
+            "WIFI:T:WPA2;S:Cafe\u{301};P:hunter\u{200B}22;;"
+        );
+        assert_eq!(
+            pasted.warnings()[1],
+            "The password contains U+200B, an invisible character. Normalizing fixes this (Credentials::normalized(), or --normalize)"
+        );
+        assert!(lint(&pasted)
+            .iter()
+            .any(|issue| issue.kind == IssueKind::Confusable));
+        // neither the position nor the letter a look-alike stands for is given for passwords
+        let spoofed = Credentials::new(
+            Some("G\u{430}st"),
+            Some("p\u{430}ssword"),
+            Some("wpa2"),
+            false,
+            false,
+        );
+        assert_eq!(
+            spoofed.warnings()[1],
+            "The password contains U+0430, a Cyrillic or Greek letter that looks like a Latin one. Normalizing fixes this (Credentials::normalized(), or --normalize)"
+        );
+        let messages: Vec<String> = lint(&spoofed)
+            .iter()
+            .map(|issue| issue.message.clone())
+            .collect();
+        assert!(messages[1].contains("at character 2, which looks like the Latin letter `a`"));
+        assert!(messages[3].starts_with("The password contains U+0430, a Cyrillic or Greek letter"));
+        assert!(!messages[3].contains("character 2") && !messages[3].contains('`'));
+
+        let normalized = pasted.normalized();
+        assert_eq!(
+            normalized.format().unwrap(),
+            "WIFI:T:WPA2;S:Café;P:hunter22;;"
+        );
+        assert!(normalized.warnings().is_empty());
+        // raw SSIDs are not text, so they are left alone
+        let raw = Credentials::new(Some(""), Some("password"), Some("wpa2"), false, false)
+            .with_ssid_bytes(&[0xca, 0xfe, 0xff]);
+        assert!(raw.warnings().is_empty());
+        assert_eq!(raw.clone().normalized(), raw);
+    }
+}
+
+/// Wifi QR code generator
+#[cfg(feature = "std")]
+pub mod code {
+    use std::error;
+    use std::io::{self, IsTerminal, Write};
+    #[cfg(feature = "raster")]
+    use std::path::Path;
+
+    #[cfg(feature = "raster")]
+    use image::{ImageBuffer, LumaA, Rgba};
+    use qrcodegen::{Mask, QrCode, QrSegment, Version};
+
+    use crate::bitmap::methods::pack_bitmap as pack_bitmap_export;
+    use crate::exporters::methods::{
+        console_qr as console_qr_export, write_console as write_console_export,
+    };
+    #[cfg(feature = "raster")]
+    use crate::exporters::methods::{
+        make_image as make_image_export, save_image as save_image_export,
+        write_png as write_png_export, write_png_1bit as write_png_1bit_export,
+    };
+    #[cfg(feature = "svg")]
+    use crate::exporters::methods::{
+        to_svg_string as to_svg_string_export, write_svg as write_svg_export,
+    };
+    #[cfg(feature = "svg")]
+    use crate::html::methods::write_html_page;
+    use crate::matrix::methods::{
+        module_matrix as module_matrix_export, write_matrix as write_matrix_export,
+    };
+    use crate::onc::methods::{from_onc_str, to_onc_string};
+    #[cfg(feature = "raster")]
+    use crate::render::methods::raster;
+    #[cfg(feature = "svg")]
+    use crate::svg::methods::{
+        write_styled_svg as write_styled_svg_export,
+        write_svg_with_options as write_svg_with_options_export,
+    };
+    #[cfg(feature = "raster")]
+    use crate::terminal::methods::{to_iterm_string, to_kitty_string, to_sixel_string};
+
+    pub use crate::bitmap::methods::{BitmapLayout, BitmapOptions};
+    pub use crate::confusable::methods::{
+        find_suspicious, normalize_text, Suspicion, SuspiciousChar,
+    };
+    pub use crate::exporters::methods::{ConsoleOptions, ConsoleStyle};
+    #[cfg(feature = "svg")]
+    pub use crate::html::methods::HtmlOptions;
+    pub use crate::lint::methods::{lint, lint_payload, Issue, IssueKind, Severity};
+    pub use crate::matrix::methods::{ErrorCorrection, MatrixFormat, QrMatrix};
+    pub use crate::onc::methods::OncError;
+    pub use crate::profile::methods::{FieldOrder, Profile};
+    pub use crate::render::methods::{
+        data_uri, Color, Length, PngMode, RasterLayout, Registry, RenderOptions, Renderer, Unit,
+        DEFAULT_DPI, MAX_RASTER_SIZE, MIN_MODULE_DOTS,
+    };
+    pub use crate::style::methods::{FinderShape, Gradient, ModuleShape, PathMode, SvgStyle};
+    #[cfg(feature = "svg")]
+    pub use crate::svg::methods::{StyleError, SvgOptions};
+    #[cfg(feature = "raster")]
+    pub use crate::terminal::methods::TerminalProtocol;
+    #[cfg(feature = "serde")]
+    pub use crate::wifi::methods::RedactedCredentials;
+    pub use crate::wifi::methods::{auth, from_hex, Credentials, FormatError, SCHEMA_VERSION};
+
+    /// generates a qrcode from a Credentials configuration. With config.utf8_ssid set, payloads
+    /// that are not plain ASCII are marked as UTF-8 with an ECI 26 designator
+    pub fn encode(config: &Credentials) -> Result<QrMatrix, Box<dyn error::Error>> {
+        let c = config.format()?;
+
+        if config.utf8_ssid && !c.is_ascii() {
+            QrMatrix::encode_utf8(&c, ErrorCorrection::High)
+        } else {
+            QrMatrix::encode_text(&c, ErrorCorrection::High)
+        }
+    }
+
+    /// manual_encode isn't intended for use externally, but exists to compare between the
+    /// automated encoder and this manual_encode version
+    /// https://docs.rs/qrcodegen/latest/src/qrcodegen/lib.rs.html#151
+    ///
+    /// versions run from 1 to 40, with lowest_version at most highest_version, and masks from 0
+    /// to 7 (None picks the best one). Anything outside those ranges is an error, as are
+    /// credentials that fail to format and payloads that don't fit the highest version
+    pub fn manual_encode(
+        config: &Credentials,
+        error_level: ErrorCorrection,
+        lowest_version: u8,
+        highest_version: u8,
+        mask_level: Option<u8>,
+    ) -> Result<QrMatrix, Box<dyn error::Error>> {
+        let versions = Version::MIN.value()..=Version::MAX.value();
+        if !versions.contains(&lowest_version)
+            || !versions.contains(&highest_version)
+            || lowest_version > highest_version
+        {
+            return Err(format!(
+                "Invalid version range {} to {}. QR code versions run from 1 to 40",
+                lowest_version, highest_version
+            )
+            .into());
+        }
+        if let Some(mask) = mask_level.filter(|&mask| mask > 7) {
+            return Err(format!("Invalid mask {}. Masks run from 0 to 7", mask).into());
+        }
+
+        let segs: Vec<QrSegment> = QrSegment::make_segments(&config.format()?);
+
+        let code = QrCode::encode_segments_advanced(
+            &segs,
+            error_level.to_qrcodegen(),
+            Version::new(lowest_version),
+            Version::new(highest_version),
+            mask_level.map(Mask::new),
+            true,
+        )?;
+        Ok(QrMatrix::from_qrcode(code))
+    }
+
+    /// generates a wifi qr code that is printed to a terminal/console for quick scanning
+    ///
+    /// parameters:
+    /// - qrcode: encoded qrcode
+    /// - quiet_zone: the border size to apply to the QR code (created with ASCII_BL_BLOCK)
+    ///
+    /// result:
+    /// - this prints a block of text directly to the console. When stdout is a terminal, the code
+    ///   and its quiet zone are colored black-on-white (see ConsoleOptions::ansi_colors), so the
+    ///   border is light on any theme
+    pub fn console_qr(qrcode: &QrMatrix, quiet_zone: i32) {
+        console_qr_with(
+            qrcode,
+            &ConsoleOptions {
+                quiet_zone,
+                ansi_colors: io::stdout().is_terminal(),
+                ..ConsoleOptions::default()
+            },
+        )
+    }
+
+    /// like console_qr, but with a choice of block characters. ConsoleStyle::Half and
+    /// ConsoleStyle::Quadrant pack more than one module into each character cell, so larger
+    /// codes still fit into a small terminal window. Colors are chosen as in console_qr
+    ///
+    /// parameters:
+    /// - qrcode: encoded qrcode
+    /// - quiet_zone: the border size to apply to the QR code, in modules
+    /// - style: the block characters to draw with
+    pub fn console_qr_styled(qrcode: &QrMatrix, quiet_zone: i32, style: ConsoleStyle) {
+        console_qr_with(
+            qrcode,
+            &ConsoleOptions {
+                quiet_zone,
+                style,
+                ansi_colors: io::stdout().is_terminal(),
+                ..ConsoleOptions::default()
+            },
+        )
+    }
+
+    /// prints a wifi qr code to the console with full control over its appearance.
+    /// Use ConsoleOptions::invert or ConsoleOptions::ansi_colors when the code comes out
+    /// inverted on a light (or dark) terminal theme
+    ///
+    /// parameters:
+    /// - qrcode: encoded qrcode
+    /// - options: quiet zone, block characters and colors to draw with
+    pub fn console_qr_with(qrcode: &QrMatrix, options: &ConsoleOptions) {
+        console_qr_export(qrcode, options)
+    }
+
+    /// like console_qr_with, but writes the text into any io::Write instead of printing it
+    ///
+    /// parameters:
+    /// - qrcode: encoded qrcode
+    /// - options: quiet zone, block characters and colors to draw with
+    /// - out: where to write the text
+    pub fn write_console<W: Write + ?Sized>(
+        qrcode: &QrMatrix,
+        options: &ConsoleOptions,
+        out: &mut W,
+    ) -> io::Result<()> {
+        write_console_export(qrcode, options, out)
+    }
+
+    #[cfg(feature = "raster")]
+    pub fn make_image(
+        qrcode: &QrMatrix,
+        scale: i32,
+        border_size: i32,
+    ) -> ImageBuffer<LumaA<u8>, Vec<u8>> {
+        make_image_export(qrcode, scale, border_size)
+    }
+
+    /// generates a color image from a QrMatrix, sized by options.size and options.dpi (e.g. 40mm at
+    /// 300 DPI, or exactly 512px), or by options.module_size when no size is given
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * options: size, resolution, quiet zone (in modules) and colors. See also
+    ///   RenderOptions::warnings()
+    #[cfg(feature = "raster")]
+    pub fn make_image_with(
+        qrcode: &QrMatrix,
+        options: &RenderOptions,
+    ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, Box<dyn error::Error>> {
+        raster(qrcode, options)
+    }
+
+    /// generates an svg string from a QrMatrix (output from encode())
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    #[cfg(feature = "svg")]
+    pub fn make_svg(qrcode: &QrMatrix) -> String {
+        to_svg_string_export(qrcode, 4)
+    }
+
+    /// writes the svg generated by make_svg into any io::Write
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * out: where to write the SVG document, e.g. a file, socket or buffer
+    #[cfg(feature = "svg")]
+    pub fn write_svg<W: Write + ?Sized>(qrcode: &QrMatrix, out: &mut W) -> io::Result<()> {
+        write_svg_export(qrcode, 4, out)
+    }
+
+    /// returns the module grid of a QrMatrix as rows of booleans (true for dark), from top to
+    /// bottom and left to right
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * border: the quiet zone, in light modules added on every side
+    pub fn module_matrix(qrcode: &QrMatrix, border: i32) -> Vec<Vec<bool>> {
+        module_matrix_export(qrcode, border)
+    }
+
+    /// writes the module grid of a QrMatrix as a JSON array of rows, a text grid of 0 and 1, or a
+    /// PBM image with one pixel per module. Dark modules are 1
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * border: the quiet zone, in light modules added on every side
+    ///
+    /// * format: see MatrixFormat
+    ///
+    /// * out: where to write the grid
+    pub fn write_matrix<W: Write + ?Sized>(
+        qrcode: &QrMatrix,
+        border: i32,
+        format: MatrixFormat,
+        out: &mut W,
+    ) -> io::Result<()> {
+        let mut out = out;
+        write_matrix_export(qrcode, border, format, &mut out)
+    }
+
+    /// packs a QrMatrix into a 1-bit bitmap for microcontrollers and e-paper displays, sized like
+    /// image output. Returns the width and height in pixels, and the bytes in options.bitmap_layout
+    /// order (set bits are dark, or light with options.invert)
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * options: size or module size, quiet zone (in modules), layout and inversion
+    pub fn pack_bitmap(
+        qrcode: &QrMatrix,
+        options: &RenderOptions,
+    ) -> Result<(u32, Vec<u8>), Box<dyn error::Error>> {
+        let layout = options.raster_layout(qrcode)?;
+        Ok((
+            layout.size,
+            pack_bitmap_export(qrcode, &layout, &options.bitmap_options()),
+        ))
+    }
+
+    /// returns a DEC Sixel escape sequence that draws an image on a sixel-capable terminal
+    ///
+    /// * image: ImageBuffer<>, e.g. from make_image()
+    #[cfg(feature = "raster")]
+    pub fn to_sixel(image: &ImageBuffer<LumaA<u8>, Vec<u8>>) -> String {
+        to_sixel_string(image)
+    }
+
+    /// returns a kitty graphics protocol escape sequence that draws an image on the terminal
+    ///
+    /// * image: ImageBuffer<>, e.g. from make_image()
+    #[cfg(feature = "raster")]
+    pub fn to_kitty(image: &ImageBuffer<LumaA<u8>, Vec<u8>>) -> Result<String, image::ImageError> {
+        to_kitty_string(image)
+    }
+
+    /// returns an iTerm2 inline image escape sequence that draws an image on the terminal
+    ///
+    /// * image: ImageBuffer<>, e.g. from make_image()
+    #[cfg(feature = "raster")]
+    pub fn to_iterm(image: &ImageBuffer<LumaA<u8>, Vec<u8>>) -> Result<String, image::ImageError> {
+        to_iterm_string(image)
+    }
+
+    /// generates a styled svg string from a QrMatrix, e.g. with dots instead of square modules,
+    /// rounded finder patterns or a gradient. Returns a StyleError instead of an unscannable code
+    /// when the colors lack contrast
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * dark: the color of dark modules
+    ///
+    /// * light: the color of light modules and the border
+    ///
+    /// * style: shapes, gradient and finder colors
+    #[cfg(feature = "svg")]
+    pub fn make_styled_svg(
+        qrcode: &QrMatrix,
+        dark: Color,
+        light: Color,
+        style: &SvgStyle,
+    ) -> Result<String, Box<dyn error::Error>> {
+        style.check(dark, light)?;
+
+        let mut svg = Vec::new();
+        write_styled_svg_export(qrcode, 4, dark, light, style, &mut svg)?;
+        Ok(String::from_utf8(svg)?)
+    }
+
+    /// generates an svg string from a QrMatrix with a custom border, physical size, colors, style
+    /// and path optimization. Returns a StyleError instead of an unscannable code when the colors
+    /// lack contrast
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * options: see SvgOptions. SvgOptions::default() merges modules into outlines, which makes
+    ///   for much smaller documents than make_svg()
+    #[cfg(feature = "svg")]
+    pub fn make_svg_with(
+        qrcode: &QrMatrix,
+        options: &SvgOptions,
+    ) -> Result<String, Box<dyn error::Error>> {
+        options.style.check(options.dark, options.light)?;
+
+        let mut svg = Vec::new();
+        write_svg_with_options_export(qrcode, options, &mut svg)?;
+        Ok(String::from_utf8(svg)?)
+    }
+
+    /// generates a self-contained HTML guest page: the code as inline SVG, the network name, the
+    /// password with a copy button (when options.include_password is set), join instructions for
+    /// common platforms, and print styles for A4 and Letter paper
+    ///
+    /// * qrcode: &QrMatrix, encoded from config
+    ///
+    /// * config: the network shown on the page
+    ///
+    /// * options: see HtmlOptions
+    #[cfg(feature = "svg")]
+    pub fn make_html(
+        qrcode: &QrMatrix,
+        config: &Credentials,
+        options: &HtmlOptions,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let mut html = Vec::new();
+        write_html(qrcode, config, options, &mut html)?;
+        Ok(String::from_utf8(html)?)
+    }
+
+    /// writes the page generated by make_html into any io::Write
+    ///
+    /// * out: where to write the page, e.g. a file or an HTTP response
+    #[cfg(feature = "svg")]
+    pub fn write_html<W: Write + ?Sized>(
+        qrcode: &QrMatrix,
+        config: &Credentials,
+        options: &HtmlOptions,
+        out: &mut W,
+    ) -> Result<(), Box<dyn error::Error>> {
+        options
+            .svg
+            .style
+            .check(options.svg.dark, options.svg.light)?;
+
+        let mut out = out;
+        write_html_page(qrcode, config, options, &mut out)
+    }
+
+    /// saves an image to a file
+    ///
+    /// * image: ImageBuffer<>
+    ///
+    /// * save_file: file path to save the image into
+    #[cfg(feature = "raster")]
+    pub fn save_image<P: AsRef<Path>>(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+        save_file: P,
+    ) -> Result<(), image::ImageError> {
+        save_image_export(image, save_file)
+    }
+
+    /// writes an image into any io::Write as a PNG file
+    ///
+    /// * image: ImageBuffer<>
+    ///
+    /// * out: where to write the PNG data, e.g. a file opened with custom permissions, or stdout
+    #[cfg(feature = "raster")]
+    pub fn write_png<W: Write + ?Sized>(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+        out: &mut W,
+    ) -> Result<(), image::ImageError> {
+        write_png_export(image, out)
+    }
+
+    /// writes an image into any io::Write as a 1-bit grayscale PNG file. These are several times
+    /// smaller than write_png() output, which suits archives, email and firmware
+    ///
+    /// * image: ImageBuffer<>, e.g. from make_image()
+    ///
+    /// * transparent: make the light modules and the quiet zone transparent
+    ///
+    /// * out: where to write the PNG data
+    #[cfg(feature = "raster")]
+    pub fn write_png_1bit<W: Write + ?Sized>(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+        transparent: bool,
+        out: &mut W,
+    ) -> Result<(), png::EncodingError> {
+        write_png_1bit_export(image, transparent, out)
+    }
+
+    /// renders a QR code in any format known to a Registry
+    ///
+    /// * qrcode: &QrMatrix
+    ///
+    /// * registry: the renderers to choose from, e.g. Registry::default()
+    ///
+    /// * format: a renderer name (`svg`, `png`, `console`, ...)
+    ///
+    /// * options: quiet zone, colors, module size and style, shared by every renderer
+    ///
+    /// * out: where to write the output
+    pub fn render<W: Write + ?Sized>(
+        qrcode: &QrMatrix,
+        registry: &Registry,
+        format: &str,
+        options: &RenderOptions,
+        out: &mut W,
+    ) -> Result<(), Box<dyn error::Error>> {
+        match registry.get(format) {
+            Some(renderer) => renderer.render(qrcode, options, &mut &mut *out),
+            None => Err(format!(
+                "Unknown output format: {}. Supported formats are: {}",
+                format,
+                registry.names().join(", ")
+            )
+            .into()),
+        }
+    }
+
+    /// renders a QR code in any format known to a Registry, as a `data:` URI
+    /// (e.g. `data:image/png;base64,...`) that can be embedded in HTML or email without
+    /// writing the image to disk
+    ///
+    /// * format: a renderer name (`svg`, `png`, `jpeg`, ...)
+    ///
+    /// See render() for the other arguments.
+    pub fn render_data_uri(
+        qrcode: &QrMatrix,
+        registry: &Registry,
+        format: &str,
+        options: &RenderOptions,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let mut data = Vec::new();
+        render(qrcode, registry, format, options, &mut data)?;
+        // render() has already checked that the format exists
+        let mime = registry.get(format).unwrap().mime_type();
+        Ok(data_uri(mime, &data))
+    }
+
+    /// returns an image, e.g. from make_image(), as a `data:image/png;base64,` URI
+    ///
+    /// * image: ImageBuffer<>
+    #[cfg(feature = "raster")]
+    pub fn make_image_data_uri(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+    ) -> Result<String, image::ImageError> {
+        let mut png = Vec::new();
+        write_png(image, &mut png)?;
+        Ok(data_uri("image/png", &png))
+    }
+
+    /// returns the svg generated by make_svg as a `data:image/svg+xml;base64,` URI
+    ///
+    /// * qrcode: &QrMatrix
+    #[cfg(feature = "svg")]
+    pub fn make_svg_data_uri(qrcode: &QrMatrix) -> String {
+        data_uri("image/svg+xml", make_svg(qrcode).as_bytes())
+    }
+
+    /// generates a ChromeOS ONC (Open Network Configuration) document for a set of credentials
+    ///
+    /// * config: &Credentials
+    ///
+    /// The output is an `UnencryptedConfiguration` with one `WiFi` entry in `NetworkConfigurations`.
+    pub fn to_onc(config: &Credentials) -> Result<String, OncError> {
+        to_onc_string(config)
+    }
+
+    /// reads every Wi-Fi network out of a ChromeOS ONC (Open Network Configuration) document
+    ///
+    /// * onc: the ONC document, as a JSON string
+    pub fn from_onc(onc: &str) -> Result<Vec<Credentials>, OncError> {
+        from_onc_str(onc)
+    }
+}
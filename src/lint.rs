@@ -0,0 +1,423 @@
+/// Compatibility checks for `WIFI:` payloads
+///
+/// The checks look for what makes a code fail on some phones rather than on all of them: values
+/// readers decode as hex, characters they mishandle, passwords an access point rejects, and codes
+/// too dense to scan from a small print. Errors mean the code won't join the network on common
+/// readers; warnings mean it might not.
+pub mod methods {
+    use std::error;
+    use std::fmt;
+
+    use crate::code::{encode, Credentials};
+    use crate::confusable::methods::find_suspicious;
+    use crate::matrix::methods::{ErrorCorrection, QrMatrix};
+    use crate::wifi::methods::is_hex_key;
+
+    /// the highest QR version that isn't reported as dense. Version 8 is 49x49 modules, enough for
+    /// a 20 character SSID with a 40 character passphrase
+    const DENSE_VERSION: u8 = 8;
+
+    /// how serious an issue is
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum Severity {
+        /// some readers may mishandle the code
+        Warning,
+        /// the code won't work on common readers, or at all
+        Error,
+    }
+
+    /// what an issue is about, for filtering in scripts
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+    pub enum IssueKind {
+        /// Credentials::format() refused the network
+        Format,
+        /// the payload doesn't start with `WIFI:`
+        Prefix,
+        /// the payload has no `S:` field
+        MissingSsid,
+        /// the payload doesn't end with `;;`
+        Terminator,
+        /// the encryption type isn't uppercase
+        LowercaseEncryption,
+        /// an unquoted SSID that readers decode as hex
+        HexSsid,
+        /// an unquoted password that readers take as a raw hex key, which may be meant as a
+        /// passphrase
+        HexPassword,
+        /// a control character, or an escaped character readers may keep the backslash of
+        Character,
+        /// non-ASCII text, which readers may decode in the wrong character set
+        Charset,
+        /// leading or trailing whitespace
+        Whitespace,
+        /// an invisible character, unusual space, detached accent or look-alike letter, usually
+        /// pasted in with the credentials
+        Confusable,
+        /// a password the access point will reject for its encryption type
+        Password,
+        /// a code too dense to scan easily, or too long to encode
+        Density,
+    }
+
+    /// a problem found in a payload
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Issue {
+        pub severity: Severity,
+        pub kind: IssueKind,
+        pub message: String,
+    }
+
+    impl Issue {
+        fn new(severity: Severity, kind: IssueKind, message: String) -> Self {
+            Issue {
+                severity,
+                kind,
+                message,
+            }
+        }
+    }
+
+    impl fmt::Display for Issue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.severity {
+                Severity::Warning => write!(f, "warning: {}", self.message),
+                Severity::Error => write!(f, "error: {}", self.message),
+            }
+        }
+    }
+
+    /// a payload field, with escapes undone and quotes removed
+    struct Field {
+        key: String,
+        value: String,
+        quoted: bool,
+    }
+
+    impl Field {
+        /// parses `K:value` as written in the payload
+        fn parse(raw: &str) -> Self {
+            let (key, value) = match raw.find(':') {
+                Some(i) => (&raw[..i], &raw[i + 1..]),
+                None => (raw, ""),
+            };
+
+            // escapes are undone first, so that `"abc\\"` is the quoted value `abc\`, while the
+            // closing quote of `"abc\"` is escaped and part of the value
+            let mut unescaped = String::new();
+            let mut last_escaped = false;
+            let mut chars = value.chars();
+            while let Some(c) = chars.next() {
+                last_escaped = c == '\\';
+                match c {
+                    '\\' => unescaped.extend(chars.next()),
+                    _ => unescaped.push(c),
+                }
+            }
+
+            let quoted = unescaped.len() >= 2
+                && value.starts_with('"')
+                && unescaped.ends_with('"')
+                && !last_escaped;
+            let value = if quoted {
+                unescaped[1..unescaped.len() - 1].to_string()
+            } else {
+                unescaped
+            };
+
+            Field {
+                key: key.to_string(),
+                value,
+                quoted,
+            }
+        }
+    }
+
+    /// splits the body of a payload at unescaped `;`. Returns the fields and whatever follows
+    /// the last `;`, which readers that need a terminator drop
+    fn split_fields(body: &str) -> (Vec<String>, String) {
+        let mut fields = Vec::new();
+        let mut current = String::new();
+        let mut chars = body.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    current.push(c);
+                    current.extend(chars.next());
+                }
+                ';' => fields.push(std::mem::take(&mut current)),
+                _ => current.push(c),
+            }
+        }
+
+        (fields, current)
+    }
+
+    /// checks the characters of an SSID or password
+    fn check_characters(name: &str, value: &str, issues: &mut Vec<Issue>) {
+        // the password is named but never shown, since issues end up in logs and terminals
+        let shown = if name == "password" {
+            name.to_string()
+        } else {
+            format!("{} `{}`", name, value)
+        };
+
+        if value.trim() != value {
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::Whitespace,
+                format!(
+                    "The {} starts or ends with whitespace, which is easy to lose when typed or copied, and some readers trim it",
+                    shown
+                ),
+            ));
+        }
+
+        if let Some(c) = value.chars().find(|c| c.is_control()) {
+            issues.push(Issue::new(
+                Severity::Error,
+                IssueKind::Character,
+                format!(
+                    "The {} contains the control character U+{:04X}, which readers drop or stop at",
+                    name, c as u32
+                ),
+            ));
+        }
+
+        if let Some(c) = value.chars().find(|&c| c == '\\' || c == '"') {
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::Character,
+                format!(
+                    "The {} contains `{}`. It is escaped with a backslash, but readers that don't undo escapes keep the backslash",
+                    name, c
+                ),
+            ));
+        }
+
+        if !value.is_ascii() {
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::Charset,
+                format!(
+                    "The {} is not plain ASCII. Without an ECI designator, some readers decode it in the wrong character set (set utf8_ssid, or pass --utf8-ssid)",
+                    shown
+                ),
+            ));
+        }
+
+        for suspect in find_suspicious(value) {
+            let fix = if suspect.is_normalized() {
+                " (Credentials::normalized(), or --normalize)"
+            } else {
+                ""
+            };
+            let suspect = if name == "password" {
+                suspect.redacted()
+            } else {
+                suspect.to_string()
+            };
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::Confusable,
+                format!(
+                    "The {} contains {}. The code carries it as it is, which may not match what the access point has{}",
+                    name, suspect, fix
+                ),
+            ));
+        }
+    }
+
+    /// checks that the access point accepts the password for the encryption type
+    fn check_password(encryption: &str, password: &str, issues: &mut Vec<Issue>) {
+        let length = password.len();
+
+        let problem = match encryption.to_uppercase().as_str() {
+            "WEP" => match length {
+                5 | 13 => None,
+                _ if is_hex_key(encryption, password) => None,
+                _ => Some(format!(
+                    "WEP keys are 5 or 13 characters, or 10, 26 or 58 hex digits, not {} bytes",
+                    length
+                )),
+            },
+            "WPA" | "WPA2" => match length {
+                8..=63 => None,
+                _ if is_hex_key(encryption, password) => None,
+                _ => Some(format!(
+                    "WPA passphrases are 8 to 63 characters (or 64 hex digits), not {} bytes",
+                    length
+                )),
+            },
+            "WPA3" | "SAE" if length < 8 => Some(format!(
+                "WPA3 passwords are at least 8 characters, not {} bytes",
+                length
+            )),
+            _ => None,
+        };
+
+        if let Some(problem) = problem {
+            issues.push(Issue::new(
+                Severity::Error,
+                IssueKind::Password,
+                format!("{}; the access point will reject this password", problem),
+            ));
+        }
+    }
+
+    /// checks a `WIFI:` string as it would be encoded, e.g. one from Credentials::format() or
+    /// read back from a printed code
+    pub fn lint_payload(payload: &str) -> Vec<Issue> {
+        lint_encoded(
+            payload,
+            QrMatrix::encode_text(payload, ErrorCorrection::High),
+        )
+    }
+
+    /// checks a payload, with the density of the code it was encoded into
+    fn lint_encoded(payload: &str, encoded: Result<QrMatrix, Box<dyn error::Error>>) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        let body = match payload.strip_prefix("WIFI:") {
+            Some(body) => body,
+            None => {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    IssueKind::Prefix,
+                    "The payload does not start with `WIFI:`, so readers won't treat it as a network".to_string(),
+                ));
+                return issues;
+            }
+        };
+
+        let (raw_fields, rest) = split_fields(body);
+        if !rest.is_empty() {
+            issues.push(Issue::new(
+                Severity::Error,
+                IssueKind::Terminator,
+                format!(
+                    "The last field `{}` is not followed by `;`, and readers drop it. End the payload with `;;`",
+                    rest
+                ),
+            ));
+        } else if raw_fields.last().map(|f| !f.is_empty()).unwrap_or(false) {
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::Terminator,
+                "The payload ends with a single `;`. End it with `;;`, as some readers expect"
+                    .to_string(),
+            ));
+        }
+
+        let fields: Vec<Field> = raw_fields
+            .iter()
+            .chain(std::iter::once(&rest))
+            .filter(|f| !f.is_empty())
+            .map(|f| Field::parse(f))
+            .collect();
+        let find = |key: &str| fields.iter().find(|f| f.key == key);
+
+        let encryption = find("T").map(|f| f.value.as_str()).unwrap_or("");
+        if encryption != "nopass" && encryption != encryption.to_uppercase() {
+            issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::LowercaseEncryption,
+                format!(
+                    "The encryption type `T:{}` is not uppercase, which fails on iOS. Write `T:{}`",
+                    encryption,
+                    encryption.to_uppercase()
+                ),
+            ));
+        }
+
+        match find("S") {
+            Some(ssid) => {
+                if !ssid.quoted
+                    && !ssid.value.is_empty()
+                    && ssid.value.chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    issues.push(Issue::new(
+                        Severity::Warning,
+                        IssueKind::HexSsid,
+                        format!(
+                            "The SSID `{}` is all hex digits, and Android and ZXing-based readers decode it as hex. Quote it (--quote, or --profile zxing)",
+                            ssid.value
+                        ),
+                    ));
+                }
+                check_characters("SSID", &ssid.value, &mut issues);
+            }
+            None => issues.push(Issue::new(
+                Severity::Error,
+                IssueKind::MissingSsid,
+                "The payload has no `S:` (SSID) field".to_string(),
+            )),
+        }
+
+        if let Some(password) = find("P") {
+            if !password.quoted && is_hex_key(encryption, &password.value) {
+                issues.push(Issue::new(
+                    Severity::Warning,
+                    IssueKind::HexPassword,
+                    format!(
+                        "The password is {} hex digits, which readers and access points take as a raw key. Quote it if it is a passphrase (--quote)",
+                        password.value.len()
+                    ),
+                ));
+            }
+            check_characters("password", &password.value, &mut issues);
+            check_password(encryption, &password.value, &mut issues);
+        }
+
+        match encoded {
+            Ok(qr) if qr.version() > DENSE_VERSION => issues.push(Issue::new(
+                Severity::Warning,
+                IssueKind::Density,
+                format!(
+                    "The payload needs a version {} code ({1}x{1} modules), which is hard to scan from small prints. Shorten the SSID or password",
+                    qr.version(),
+                    qr.size()
+                ),
+            )),
+            Ok(_) => {}
+            Err(e) => issues.push(Issue::new(
+                Severity::Error,
+                IssueKind::Density,
+                format!("The payload does not fit in a QR code: {}", e),
+            )),
+        }
+
+        issues
+    }
+
+    /// checks a network for compatibility issues, as formatted with its profile. Issues the
+    /// credentials already take care of are left out: hex SSIDs when raw_ssid is set, non-ASCII
+    /// text when utf8_ssid is set, and a lowercase `T:` when the profile writes the type as given
+    pub fn lint(config: &Credentials) -> Vec<Issue> {
+        let payload = match config.format() {
+            Ok(payload) => payload,
+            Err(e) => {
+                return vec![Issue::new(
+                    Severity::Error,
+                    IssueKind::Format,
+                    e.to_string(),
+                )];
+            }
+        };
+
+        // sized with the encoder encode() picks, since utf8_ssid adds an ECI designator
+        lint_encoded(&payload, encode(config))
+            .into_iter()
+            .filter(|issue| !(issue.kind == IssueKind::HexSsid && config.raw_ssid.is_some()))
+            .filter(|issue| !(issue.kind == IssueKind::Charset && config.utf8_ssid))
+            .filter(|issue| {
+                issue.kind != IssueKind::LowercaseEncryption
+                    || config.profile.uppercase_encryption()
+            })
+            .collect()
+    }
+}
@@ -3,34 +3,44 @@ extern crate rpassword;
 extern crate wifiqr;
 use std::fs;
 use std::io;
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufRead, IsTerminal, Write};
 
-use clap::{App, Arg, ArgGroup};
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
 use rpassword::prompt_password_stdout;
 
 fn main() {
     let options = App::new("WifiQR")
-        .version("0.0.8")
+        .version("0.0.9")
         .about("Encode your wi-fi credentials as a scannable QR code")
         .author("davidk")
         .usage("wifiqr [ --ssid (ssid) ] [ --password (password) | --ask ]
             [ --encr encryption type (default:wpa2) ]
-            [ --imagefile (output_name.png) | --svg | --svgfile (output_name.svg) ]")
+            [ --onc (network.onc) ]
+            [ --imagefile (output_name.png) | --svg | --svgfile (output_name.svg) | --oncfile (output_name.onc) | --htmlfile (output_name.html) ]
+    wifiqr [ --ssid (ssid) ... ] lint [ --deny-warnings ] [ WIFI:... | - ]")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("ssid")
                 .long("ssid")
                 .takes_value(true)
-                .required(true)
+                .required_unless_one(&["onc", "ssid_hex"])
                 .display_order(1)
-                .help("Sets the WiFi SSID"),
+                .help("Sets the WiFi SSID (with --onc, selects which network to use from the ONC file)"),
+        )
+        .arg(
+            Arg::with_name("ssid_hex")
+                .long("ssid-hex")
+                .takes_value(true)
+                .conflicts_with_all(&["ssid", "onc"])
+                .display_order(2)
+                .help("Sets the WiFi SSID from its octets in hex, for network names that are not UTF-8 (e.g. --ssid-hex 836583588367)"),
         )
         .arg(
             Arg::with_name("password")
                 .long("password")
                 .takes_value(true)
                 .default_value("")
-                .display_order(2)
+                .display_order(3)
                 .help("Sets the WiFi password"),
         )
         .arg(
@@ -38,13 +48,13 @@ fn main() {
                 .long("encr")
                 .takes_value(true)
                 .default_value("wpa2")
-                .display_order(3)
+                .display_order(4)
                 .help("The WiFi's encryption type (wpa, wpa2, nopass)"),
         )
         .arg(
             Arg::with_name("hidden")
                 .long("hidden")
-                .display_order(4)
+                .display_order(5)
                 .takes_value(false)
                 .help("Optional: Indicate whether or not the SSID is hidden"),
         )
@@ -53,55 +63,96 @@ fn main() {
                 .long("scale")
                 .takes_value(true)
                 .default_value("10")
-                .display_order(5)
+                .display_order(6)
                 .help("QR code scaling factor"),
         )
         .arg(
             Arg::with_name("quiet_zone")
                 .long("quietzone")
                 .takes_value(true)
-                .display_order(6)
-                .default_value("2")
-                .help("QR code: The size of the quiet zone/border to apply to the final QR code"),
+                .display_order(7)
+                .help("QR code: The size of the quiet zone/border (in modules) to apply to the final QR code [default: 2, or 4 for SVG and HTML output]. Image output measured it in pixels before 0.0.9"),
         )
         .arg(
             Arg::with_name("image_file")
                 .long("imagefile")
                 .takes_value(true)
-                .display_order(7)
-                .help("The name of the file to save to (e.g. --imagefile qr.png). Formats: [png, jpg, bmp]"),
+                .display_order(8)
+                .help("The name of the file to save to (e.g. --imagefile qr.png), or - to write a PNG to standard output. Formats: [png, jpg, webp, gif, bmp, tiff, ico, pbm, pnm, h (C header), xbm]"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .display_order(9)
+                .help("The output format for --imagefile and --datauri, instead of guessing it from the file extension (e.g. png, jpeg, webp, gif, bmp, tiff, ico, pbm, pnm, c, xbm, svg, console)")
         )
         .arg(
             Arg::with_name("svg")
                 .long("svg")
                 .takes_value(false)
-                .display_order(8)
+                .display_order(10)
                 .help("Emit the QR code as an SVG (to standard output)")
         )
         .arg(
             Arg::with_name("svg_file")
                 .long("svgfile")
                 .takes_value(true)
-                .display_order(9)
+                .display_order(11)
                 .help("Save the QR code to a file (SVG formatted)")
         )
         .arg(
             Arg::with_name("console")
                 .long("console")
-                .display_order(10)
+                .display_order(12)
                 .help("Print the QR code out to the console")
         )
+        .arg(
+            Arg::with_name("data_uri")
+                .long("datauri")
+                .display_order(13)
+                .help("Print the QR code as a data: URI (PNG, or the --format given), for embedding in HTML or email without writing a file")
+        )
+        .arg(
+            Arg::with_name("matrix")
+                .long("matrix")
+                .takes_value(true)
+                .possible_values(&["json", "text", "pbm"])
+                .display_order(14)
+                .help("Print the raw module grid (1 = dark), including the quiet zone, as a JSON array of rows, a text grid of 0 and 1, or a PBM image with one pixel per module")
+        )
+        .arg(
+            Arg::with_name("onc_file")
+                .long("oncfile")
+                .takes_value(true)
+                .display_order(15)
+                .help("Save the network as a ChromeOS ONC (Open Network Configuration) file")
+        )
+        .arg(
+            Arg::with_name("html_file")
+                .long("htmlfile")
+                .takes_value(true)
+                .display_order(16)
+                .help("Save a printable HTML guest page with the QR code, network name and join instructions")
+        )
+        .arg(
+            Arg::with_name("show_password")
+                .long("show-password")
+                .requires("html_file")
+                .display_order(17)
+                .help("Show the password, with a copy button, on the --htmlfile page")
+        )
         .group(
             ArgGroup::with_name("output types")
                 .required(true)
-                .args(&["image_file","svg","svg_file","console"])
+                .args(&["image_file","svg","svg_file","console","data_uri","matrix","onc_file","html_file"])
         )
         .arg(
             Arg::with_name("debug")
                 .long("debug")
                 .short("d")
                 .takes_value(false)
-                .display_order(11)
+                .display_order(18)
                 .help("Display some extra debugging output")
         )
         .arg(
@@ -109,142 +160,685 @@ fn main() {
                 .long("ask")
                 .short("a")
                 .takes_value(false)
-                .display_order(12)
+                .display_order(19)
                 .help("Ask for password instead of getting it through the command-line")
         )
         .arg(
             Arg::with_name("ask-echo")
                 .long("ask-echo")
                 .takes_value(false)
-                .display_order(13)
+                .display_order(20)
                 .help("Ask for password while displaying input on the console")
         )
         .arg(
             Arg::with_name("quote")
                 .long("quote")
                 .takes_value(false)
-                .display_order(14)
+                .display_order(21)
                 .help("If the SSID or password could be mistaken for a hexadecimal value, 
                     this option will add double-quotes around the SSID and password")
         )
+        .arg(
+            Arg::with_name("utf8_ssid")
+                .long("utf8-ssid")
+                .takes_value(false)
+                .display_order(22)
+                .help("Mark the QR code as UTF-8 (with an ECI designator) when the SSID or password is not plain ASCII, so scanners don't guess another character set (e.g. for Japanese network names)")
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .possible_values(&["max-compat", "ios", "android", "zxing", "strict"])
+                .default_value("max-compat")
+                .display_order(23)
+                .help("The scanner to format the Wi-Fi string for: the case of the encryption type, quoting of hex-looking values, escaping of : and , whether H:false is written, and the field order. See the README for what each profile does")
+        )
+        .arg(
+            Arg::with_name("normalize")
+                .long("normalize")
+                .takes_value(false)
+                .display_order(24)
+                .help("Clean up a copied SSID and password: remove invisible characters (e.g. zero-width spaces), turn no-break and other unusual spaces into plain ones, compose accents stored apart from their letter, and replace Cyrillic or Greek look-alikes among Latin letters")
+        )
+        .arg(
+            Arg::with_name("console_style")
+                .long("console-style")
+                .takes_value(true)
+                .possible_values(&["full", "half", "quadrant"])
+                .default_value("full")
+                .display_order(25)
+                .help("The block characters used by --console. half and quadrant fit larger codes into small terminals")
+        )
+        .arg(
+            Arg::with_name("invert")
+                .long("invert")
+                .takes_value(false)
+                .display_order(26)
+                .help("Swap dark and light modules in --console output (for terminals where the code appears inverted). In C header and XBM output, set the bits of light pixels, for displays where 1 is white")
+        )
+        .arg(
+            Arg::with_name("console_color")
+                .long("console-color")
+                .takes_value(false)
+                .display_order(27)
+                .help("Use ANSI colors in --console output to force a black-on-white code and quiet zone, regardless of the terminal theme. This is the default when standard output is a terminal; the flag also colors output sent to a file or pipe")
+        )
+        .arg(
+            Arg::with_name("no_console_color")
+                .long("no-console-color")
+                .takes_value(false)
+                .conflicts_with("console_color")
+                .display_order(28)
+                .help("Don't color --console output, even on a terminal")
+        )
+        .arg(
+            Arg::with_name("console_protocol")
+                .long("console-protocol")
+                .takes_value(true)
+                .possible_values(&["auto", "sixel", "kitty", "iterm", "blocks"])
+                .default_value("blocks")
+                .display_order(29)
+                .help("How --console draws the QR code: as an inline image (sixel, kitty, iterm), with block characters, or auto-detected")
+        )
+        .arg(
+            Arg::with_name("dark_color")
+                .long("dark-color")
+                .takes_value(true)
+                .default_value("#000000")
+                .display_order(30)
+                .help("The color of dark modules in SVG and image output (e.g. #1A2B3C)")
+        )
+        .arg(
+            Arg::with_name("light_color")
+                .long("light-color")
+                .takes_value(true)
+                .default_value("#FFFFFF")
+                .display_order(31)
+                .help("The color of light modules and the quiet zone in SVG and image output")
+        )
+        .arg(
+            Arg::with_name("module_shape")
+                .long("module-shape")
+                .takes_value(true)
+                .possible_values(&["square", "rounded", "dot"])
+                .default_value("square")
+                .display_order(32)
+                .help("SVG: The shape of data modules")
+        )
+        .arg(
+            Arg::with_name("finder_shape")
+                .long("finder-shape")
+                .takes_value(true)
+                .possible_values(&["square", "rounded", "circle"])
+                .default_value("square")
+                .display_order(33)
+                .help("SVG: The shape of the three finder patterns in the corners")
+        )
+        .arg(
+            Arg::with_name("finder_color")
+                .long("finder-color")
+                .takes_value(true)
+                .display_order(34)
+                .help("SVG: The color of the finder patterns (defaults to --dark-color)")
+        )
+        .arg(
+            Arg::with_name("gradient")
+                .long("gradient")
+                .takes_value(true)
+                .possible_values(&["linear", "radial"])
+                .requires("gradient_color")
+                .display_order(35)
+                .help("SVG: Fill data modules with a gradient from --dark-color to --gradient-color")
+        )
+        .arg(
+            Arg::with_name("gradient_color")
+                .long("gradient-color")
+                .takes_value(true)
+                .display_order(36)
+                .help("SVG: The end color of --gradient")
+        )
+        .arg(
+            Arg::with_name("size")
+                .long("size")
+                .takes_value(true)
+                .display_order(37)
+                .help("The width and height of SVG and image output, e.g. 40mm, 1.5in or 512px (images pick the largest module size that fits, instead of --scale). Images are at most 16384 pixels wide")
+        )
+        .arg(
+            Arg::with_name("dpi")
+                .long("dpi")
+                .takes_value(true)
+                .display_order(38)
+                .help("The resolution of image output, in dots per inch. Converts --size into pixels, and is written into PNG and JPEG metadata")
+        )
+        .arg(
+            Arg::with_name("min_module_dots")
+                .long("min-module-dots")
+                .takes_value(true)
+                .default_value("4")
+                .display_order(39)
+                .help("Warn when modules printed at --dpi would be narrower than this many printer dots")
+        )
+        .arg(
+            Arg::with_name("png_mode")
+                .long("png-mode")
+                .takes_value(true)
+                .possible_values(&["palette", "gray", "rgba"])
+                .default_value("palette")
+                .display_order(40)
+                .help("How PNG images are encoded: 1-bit with the dark and light colors (smallest), 1-bit black and white, or 8-bit RGBA")
+        )
+        .arg(
+            Arg::with_name("transparent")
+                .long("transparent")
+                .display_order(41)
+                .help("Make the light modules and the quiet zone of PNG images transparent")
+        )
+        .arg(
+            Arg::with_name("bitmap_layout")
+                .long("bitmap-layout")
+                .takes_value(true)
+                .possible_values(&["rows", "columns"])
+                .default_value("rows")
+                .display_order(42)
+                .help("C header (.h): Pack pixels row by row (Adafruit GFX, most e-paper controllers) or column by column (panels addressed in portrait), most significant bit first. With --invert, set bits are white")
+        )
+        .arg(
+            Arg::with_name("bitmap_name")
+                .long("bitmap-name")
+                .takes_value(true)
+                .display_order(43)
+                .help("C header and XBM: The name of the array and its width/height defines [default: wifi_qr]")
+        )
+        .arg(
+            Arg::with_name("svg_paths")
+                .long("svg-paths")
+                .takes_value(true)
+                .possible_values(&["outlines", "runs", "modules"])
+                .default_value("outlines")
+                .display_order(44)
+                .help("SVG: Merge dark modules into outlines (smallest), horizontal runs, or write one square per module")
+        )
+        .arg(
+            Arg::with_name("title")
+                .long("title")
+                .takes_value(true)
+                .display_order(45)
+                .help("SVG: The text alternative read out by screen readers (defaults to the network name and security, e.g. \"Wi-Fi network Guest, WPA2\")")
+        )
+        .arg(
+            Arg::with_name("label_password")
+                .long("label-password")
+                .display_order(46)
+                .help("SVG: Include the password in the description read out by screen readers")
+        )
+        .arg(
+            Arg::with_name("onc")
+                .long("onc")
+                .takes_value(true)
+                .display_order(47)
+                .conflicts_with_all(&["password", "encryption", "hidden", "ask", "ask-echo", "quote"])
+                .help("Read the network from a ChromeOS ONC (Open Network Configuration) file instead of the command-line")
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Check the network given before `lint`, or WIFI: strings, for issues that make scanners fail. Exits with status 1 on errors, and 2 when there is nothing to check or the input can't be read")
+                .arg(
+                    Arg::with_name("payloads")
+                        .multiple(true)
+                        .help("WIFI: strings to check, or - to read one per line from standard input")
+                )
+                .arg(
+                    Arg::with_name("deny_warnings")
+                        .long("deny-warnings")
+                        .help("Exit with status 1 on warnings too")
+                )
+        )
         .get_matches();
 
+    if let Some(lint) = options.subcommand_matches("lint") {
+        if let Some(payloads) = lint.values_of("payloads") {
+            let mut lines: Vec<String> = Vec::new();
+            for payload in payloads {
+                if payload == "-" {
+                    for line in io::stdin().lock().lines() {
+                        match line {
+                            Ok(line) => lines.push(line),
+                            Err(e) => {
+                                println!("Unable to read standard input: {}", e);
+                                std::process::exit(2);
+                            }
+                        }
+                    }
+                } else {
+                    lines.push(payload.to_string());
+                }
+            }
+            let lines: Vec<&String> = lines.iter().filter(|l| !l.trim().is_empty()).collect();
+
+            let mut failed = false;
+            let mut clean = true;
+            for (i, line) in lines.iter().enumerate() {
+                let issues = wifiqr::code::lint_payload(line);
+                let name = format!("payload {}", i + 1);
+                failed |= report_issues(
+                    if lines.len() > 1 { Some(&name) } else { None },
+                    &issues,
+                    lint.is_present("deny_warnings"),
+                );
+                clean &= issues.is_empty();
+            }
+            if clean {
+                println!("No issues found");
+            }
+            std::process::exit(if failed { 1 } else { 0 });
+        }
+
+        if !(options.is_present("ssid")
+            || options.is_present("ssid_hex")
+            || options.is_present("onc"))
+        {
+            println!("Pass WIFI: strings to lint, or a network before the subcommand (e.g. wifiqr --ssid guest --password hunter22 lint)");
+            std::process::exit(2);
+        }
+    }
+
     // Note: avoid turbofish/generic on parse() through upfront declaration
     let scale: i32 = options.value_of("scale").unwrap_or("10").parse().unwrap();
-    let quiet_zone: i32 = options
-        .value_of("quiet_zone")
-        .unwrap_or("10")
-        .parse()
-        .unwrap();
+    let quiet_zone: Option<i32> = options.value_of("quiet_zone").map(|q| q.parse().unwrap());
     let image_file: String = options
         .value_of("image_file")
         .unwrap_or("")
         .parse()
         .unwrap();
 
-    let mut password = String::new();
+    let mut config = if options.is_present("onc") {
+        let onc_file = options.value_of("onc").unwrap();
+
+        let onc = match fs::read_to_string(onc_file) {
+            Ok(o) => o,
+            Err(e) => {
+                println!("Unable to read ONC file {}: {}", onc_file, e);
+                std::process::exit(1);
+            }
+        };
+
+        let networks = match wifiqr::code::from_onc(&onc) {
+            Ok(n) => n,
+            Err(e) => {
+                println!("There was a problem reading the ONC file.\n{}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let network = match options.value_of("ssid") {
+            Some(ssid) => networks.into_iter().find(|n| n.ssid == ssid),
+            None => networks.into_iter().next(),
+        };
+
+        match network {
+            Some(n) => n,
+            None => {
+                println!(
+                    "No matching WiFi network was found in ONC file {}",
+                    onc_file
+                );
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let ssid_bytes: Vec<u8> = match options.value_of("ssid_hex") {
+            Some(hex) => match wifiqr::code::from_hex(hex) {
+                Some(b) => b,
+                None => {
+                    println!("--ssid-hex `{}` is not a valid hex string", hex);
+                    std::process::exit(1);
+                }
+            },
+            None => options.value_of("ssid").unwrap().as_bytes().to_vec(),
+        };
+        let ssid = String::from_utf8_lossy(&ssid_bytes).into_owned();
+        let mut password = String::new();
 
-    if options.is_present("ask") {
-        password = prompt_password_stdout(
-            format!(
-                "Enter password for network `{}` (will not echo to screen): ",
-                options.value_of("ssid").unwrap()
+        if options.is_present("ask") {
+            password = prompt_password_stdout(
+                format!(
+                    "Enter password for network `{}` (will not echo to screen): ",
+                    ssid
+                )
+                .as_str(),
             )
-            .as_str(),
+            .unwrap();
+        } else if options.is_present("ask-echo") {
+            print!(
+                "Enter password for network `{}` (will echo to screen): ",
+                ssid
+            );
+
+            io::stdout().flush().unwrap();
+            io::stdin()
+                .read_line(&mut password)
+                .expect("Failed to read password");
+            password = password.trim().to_string();
+        } else {
+            password = options.value_of("password").unwrap().to_string();
+        }
+
+        wifiqr::code::auth(
+            Some(&ssid),
+            Some(&password),
+            options.value_of("encryption"),
+            options.is_present("hidden"),
+            options.is_present("quote"),
         )
-        .unwrap();
-    } else if options.is_present("ask-echo") {
-        print!(
-            "Enter password for network `{}` (will echo to screen): ",
-            options.value_of("ssid").unwrap()
-        );
+        .with_ssid_bytes(&ssid_bytes)
+    };
+    config.utf8_ssid = options.is_present("utf8_ssid");
+    config.profile = options.value_of("profile").unwrap().parse().unwrap();
+    if options.is_present("normalize") {
+        config = config.normalized();
+    }
 
-        io::stdout().flush().unwrap();
-        io::stdin()
-            .read_line(&mut password)
-            .expect("Failed to read password");
-        password = password.trim().to_string();
-    } else {
-        password = options.value_of("password").unwrap().to_string();
+    if let Some(lint) = options.subcommand_matches("lint") {
+        let issues = wifiqr::code::lint(&config);
+        let failed = report_issues(None, &issues, lint.is_present("deny_warnings"));
+        if issues.is_empty() {
+            println!("No issues found");
+        }
+        std::process::exit(if failed { 1 } else { 0 });
     }
 
-    let config = wifiqr::code::auth(
-        options.value_of("ssid"),
-        Some(&password),
-        options.value_of("encryption"),
-        options.is_present("hidden"),
-        options.is_present("quote"),
-    );
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
 
+    // debug output goes to stderr, so it never ends up inside an image written to stdout
     if options.is_present("debug") {
-        println!(
-            "SSID: {} | PASSWORD: {} | ENCRYPTION: {} | HIDDEN: {} | QUOTE SSID/PASSWORD: {}",
-            options.value_of("ssid").unwrap(),
-            password,
-            options.value_of("encryption").unwrap(),
-            options.is_present("hidden"),
-            options.is_present("quote"),
+        eprintln!(
+            "SSID: {} | PASSWORD: {} | ENCRYPTION: {} | HIDDEN: {} | QUOTE SSID/PASSWORD: {} | PROFILE: {}",
+            config.ssid, config.pass, config.encr, config.hidden, config.quote, config.profile,
         );
 
-        println!("Wifi string: {:?}", config.format().unwrap());
+        eprintln!("Wifi string: {:?}", config.format().unwrap());
     }
 
     let encoding = match wifiqr::code::encode(&config) {
         Ok(e) => e,
         Err(e) => {
             println!("There was a problem generating the QR code.\n{}", e);
-            return;
+            std::process::exit(1);
         }
     };
 
-    if options.is_present("svg_file") {
-        println!("Generating QR code ..");
-        let file_name = options.value_of("svg_file").unwrap();
+    if options.is_present("onc_file") {
+        let file_name = options.value_of("onc_file").unwrap();
 
-        println!("Writing out to SVG file: {} ..", file_name);
-        let svg_data = wifiqr::code::make_svg(&encoding);
+        let onc_data = match wifiqr::code::to_onc(&config) {
+            Ok(o) => o,
+            Err(e) => {
+                println!("There was a problem generating the ONC file.\n{}", e);
+                std::process::exit(1);
+            }
+        };
 
-        fs::write(file_name, svg_data).expect("Unable to write file");
-    } else if options.is_present("image_file") {
+        println!("Writing out to ONC file: {} ..", file_name);
+        fs::write(file_name, onc_data).expect("Unable to write file");
+        return;
+    }
 
-        // Validate that image_file extension supplied is compatible with upstream library export formats
-        match Path::new(&image_file).extension() {
-            None => {
-                println!("Error: No extension found for image file. Try --imagefile [ qr.jpeg | qr.png ] instead.");
-                return;
-            }
-            Some(p) => {
-                let ext: &str = p.to_str().unwrap();
-                match ext {
-                    "png" | "jpeg" | "jpg" => {}
-                    _ => {
-                        println!("Unrecognized file extension: {}. Try --imagefile [ qr.png | qr.jpeg | qr.jpg ] instead.", ext);
-                        return;
-                    }
-                }
+    let registry = wifiqr::code::Registry::default();
+
+    // SVG and HTML keep the 4 module quiet zone the QR spec asks for, as they did before
+    // --quietzone applied to them
+    let vector_output = options.is_present("svg")
+        || options.is_present("svg_file")
+        || options.is_present("html_file")
+        || options.value_of("format") == Some("svg")
+        || registry.for_path(&image_file).map(|r| r.name()) == Some("svg");
+    let quiet_zone = quiet_zone.unwrap_or(if vector_output { 4 } else { 2 });
+
+    // Colors are validated up front, so that a typo doesn't leave a half-written file behind
+    let color = |name: &str| -> Option<wifiqr::code::Color> {
+        options.value_of(name).map(|c| match c.parse() {
+            Ok(c) => c,
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
             }
-        };
+        })
+    };
 
-        println!("Generating QR code ..");
+    let svg_style = wifiqr::code::SvgStyle {
+        module_shape: options.value_of("module_shape").unwrap().parse().unwrap(),
+        finder_shape: options.value_of("finder_shape").unwrap().parse().unwrap(),
+        gradient: match (options.value_of("gradient"), color("gradient_color")) {
+            (Some("linear"), Some(to)) => Some(wifiqr::code::Gradient::Linear { to }),
+            (Some("radial"), Some(to)) => Some(wifiqr::code::Gradient::Radial { to }),
+            _ => None,
+        },
+        finder_color: color("finder_color"),
+        ..wifiqr::code::SvgStyle::default()
+    };
 
-        println!("Parameters: scale {} + quiet zone: {} ", scale, quiet_zone);
+    let render_options = wifiqr::code::RenderOptions {
+        quiet_zone,
+        module_size: scale,
+        style: options.value_of("console_style").unwrap().parse().unwrap(),
+        invert: options.is_present("invert"),
+        ansi_colors: options.is_present("console_color")
+            || (!options.is_present("no_console_color") && io::stdout().is_terminal()),
+        dark_color: color("dark_color").unwrap(),
+        light_color: color("light_color").unwrap(),
+        svg_style,
+        svg_paths: options.value_of("svg_paths").unwrap().parse().unwrap(),
+        size: options.value_of("size").map(|s| match s.parse() {
+            Ok(s) => s,
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        }),
+        dpi: options.value_of("dpi").map(|d| match d.parse() {
+            Ok(d) if d > 0 => d,
+            _ => {
+                println!("Invalid DPI: {}. Try a value like 300", d);
+                std::process::exit(1);
+            }
+        }),
+        min_module_dots: match options.value_of("min_module_dots").unwrap().parse() {
+            Ok(dots) if dots > 0 => dots,
+            _ => {
+                println!(
+                    "Invalid --min-module-dots: {}. Try a value like 4",
+                    options.value_of("min_module_dots").unwrap()
+                );
+                std::process::exit(1);
+            }
+        },
+        png_mode: options.value_of("png_mode").unwrap().parse().unwrap(),
+        transparent: options.is_present("transparent"),
+        bitmap_layout: options.value_of("bitmap_layout").unwrap().parse().unwrap(),
+        bitmap_name: options.value_of("bitmap_name").map(String::from),
+        title: Some(
+            options
+                .value_of("title")
+                .map(String::from)
+                .unwrap_or_else(|| config.accessible_name()),
+        ),
+        description: Some(config.accessible_description(options.is_present("label_password"))),
+    };
+
+    if options.is_present("html_file") {
+        let file_name = options.value_of("html_file").unwrap();
+
+        let html_options = wifiqr::code::HtmlOptions {
+            include_password: options.is_present("show_password"),
+            svg: render_options.svg_options(),
+            ..wifiqr::code::HtmlOptions::default()
+        };
 
-        let image = wifiqr::code::make_image(&encoding, scale, quiet_zone);
-        match wifiqr::code::save_image(&image, image_file.to_string()) {
-            Ok(_) => {
-                println!("QR code has been saved to file {}", image_file);
+        let html = match wifiqr::code::make_html(&encoding, &config, &html_options) {
+            Ok(h) => h,
+            Err(e) => {
+                println!("There was a problem generating the HTML page.\n{}", e);
+                std::process::exit(1);
             }
+        };
+
+        println!("Writing out to HTML file: {} ..", file_name);
+        fs::write(file_name, html).expect("Unable to write file");
+        return;
+    }
+
+    if options.is_present("data_uri") {
+        let format = options.value_of("format").unwrap_or("png");
+
+        match wifiqr::code::render_data_uri(&encoding, &registry, format, &render_options) {
+            Ok(uri) => println!("{}", uri),
             Err(e) => {
-                println!("Error: {:?}", e);
-                println!("Unable to write QR image to file in requested format. Supported extensions are .jpeg and .png. Try --imagefile qr.jpeg or --imagefile qr.png");
-                fs::remove_file(image_file).unwrap();
+                println!("There was a problem generating the data URI.\n{}", e);
+                std::process::exit(1);
             }
+        }
+        return;
+    }
+
+    if let Some(format) = options.value_of("matrix") {
+        let format: wifiqr::code::MatrixFormat = format.parse().unwrap();
+
+        if let Err(e) =
+            wifiqr::code::write_matrix(&encoding, quiet_zone, format, &mut io::stdout().lock())
+        {
+            println!("There was a problem writing the module matrix.\n{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Pick a renderer (by name), and the file to write to (None for standard output)
+    let (format, output_file) = if options.is_present("svg_file") {
+        ("svg", options.value_of("svg_file"))
+    } else if image_file == "-" {
+        (options.value_of("format").unwrap_or("png"), None)
+    } else if options.is_present("image_file") {
+        let format = match options.value_of("format") {
+            Some(f) => f,
+            None => match registry.for_path(&image_file) {
+                Some(r) => r.name(),
+                None => {
+                    println!(
+                        "Unrecognized file extension for {}. Supported extensions are: {} (or choose one with --format)",
+                        image_file,
+                        registry.extensions().join(", ")
+                    );
+                    std::process::exit(1);
+                }
+            },
         };
+        (format, Some(image_file.as_str()))
     } else if options.is_present("svg") {
-        println!("{}", wifiqr::code::make_svg(&encoding));
+        ("svg", None)
     } else if options.is_present("console") {
-        wifiqr::code::console_qr(&encoding, quiet_zone);
+        use wifiqr::code::TerminalProtocol;
+
+        let protocol: TerminalProtocol = options
+            .value_of("console_protocol")
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        match protocol.resolve() {
+            TerminalProtocol::Sixel => ("sixel", None),
+            TerminalProtocol::Kitty => ("kitty", None),
+            TerminalProtocol::Iterm => ("iterm", None),
+            _ => ("console", None),
+        }
     } else {
         println!("Please select an output format. For available formats, re-run with --help");
+        std::process::exit(1);
+    };
+
+    let renderer = match registry.get(format) {
+        Some(r) => r,
+        None => {
+            println!(
+                "Unknown output format: {}. Supported formats are: {}",
+                format,
+                registry.names().join(", ")
+            );
+            std::process::exit(1);
+        }
+    };
+
+    if render_options.dpi.is_some() {
+        for warning in render_options.warnings(&encoding) {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+
+    match output_file {
+        Some(file_name) => {
+            println!("Generating QR code ..");
+            match options.value_of("size") {
+                Some(size) => println!("Parameters: size {} + quiet zone: {} ", size, quiet_zone),
+                None => println!("Parameters: scale {} + quiet zone: {} ", scale, quiet_zone),
+            }
+
+            let mut file = match fs::File::create(file_name) {
+                Ok(file) => io::BufWriter::new(file),
+                Err(e) => {
+                    println!("Unable to create file {}: {}", file_name, e);
+                    std::process::exit(1);
+                }
+            };
+
+            let written = renderer
+                .render(&encoding, &render_options, &mut file)
+                .and_then(|_| file.flush().map_err(|e| e.into()));
+            match written {
+                Ok(_) => {
+                    println!("QR code has been saved to file {}", file_name);
+                }
+                Err(e) => {
+                    println!("Error: {:?}", e);
+                    println!(
+                        "Unable to write QR code to file {} in {} format.",
+                        file_name,
+                        renderer.name()
+                    );
+                    drop(file);
+                    if let Err(e) = fs::remove_file(file_name) {
+                        println!("Unable to remove the incomplete file {}: {}", file_name, e);
+                    }
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => {
+            let mut out = io::BufWriter::new(io::stdout().lock());
+            let written = renderer
+                .render(&encoding, &render_options, &mut out)
+                .and_then(|_| out.flush().map_err(|e| e.into()));
+            if let Err(e) = written {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
+
+/// prints the issues lint found, prefixed with the name of the payload when checking several, and
+/// returns whether they should fail the run
+fn report_issues(name: Option<&str>, issues: &[wifiqr::code::Issue], deny_warnings: bool) -> bool {
+    for issue in issues {
+        match name {
+            Some(name) => println!("{}: {}", name, issue),
+            None => println!("{}", issue),
+        }
+    }
+
+    issues
+        .iter()
+        .any(|issue| deny_warnings || issue.severity == wifiqr::code::Severity::Error)
+}
@@ -0,0 +1,253 @@
+/// The module grid of a QR code, for LED panels and custom renderers
+///
+/// QrMatrix is the encoded code every renderer takes. It wraps the encoder's own type, so callers
+/// don't depend on the qrcodegen version this crate was built with.
+///
+/// Each format lists the rows from top to bottom, left to right, with 1 for a dark module and 0
+/// for a light one. The quiet zone is included as rows and columns of light modules.
+pub mod methods {
+    use std::error;
+    use std::fmt;
+    use std::io::{self, Write};
+    use std::str::FromStr;
+
+    use qrcodegen::{QrCode, QrCodeEcc, QrSegment};
+
+    use crate::render::methods::pack_bits;
+
+    /// how much of a QR code can be damaged or covered and still be read. Higher levels make
+    /// larger codes for the same payload
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum ErrorCorrection {
+        /// about 7% of the codewords can be restored
+        Low,
+        /// about 15% of the codewords can be restored
+        Medium,
+        /// about 25% of the codewords can be restored
+        Quartile,
+        /// about 30% of the codewords can be restored
+        High,
+    }
+
+    impl ErrorCorrection {
+        pub(crate) fn to_qrcodegen(self) -> QrCodeEcc {
+            match self {
+                ErrorCorrection::Low => QrCodeEcc::Low,
+                ErrorCorrection::Medium => QrCodeEcc::Medium,
+                ErrorCorrection::Quartile => QrCodeEcc::Quartile,
+                ErrorCorrection::High => QrCodeEcc::High,
+            }
+        }
+
+        fn from_qrcodegen(ecc: QrCodeEcc) -> Self {
+            match ecc {
+                QrCodeEcc::Low => ErrorCorrection::Low,
+                QrCodeEcc::Medium => ErrorCorrection::Medium,
+                QrCodeEcc::Quartile => ErrorCorrection::Quartile,
+                QrCodeEcc::High => ErrorCorrection::High,
+            }
+        }
+    }
+
+    impl FromStr for ErrorCorrection {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "l" | "low" => Ok(ErrorCorrection::Low),
+                "m" | "medium" => Ok(ErrorCorrection::Medium),
+                "q" | "quartile" => Ok(ErrorCorrection::Quartile),
+                "h" | "high" => Ok(ErrorCorrection::High),
+                _ => Err(format!(
+                    "Unknown error correction level: {}. Try one of: low, medium, quartile, high",
+                    s
+                )),
+            }
+        }
+    }
+
+    impl fmt::Display for ErrorCorrection {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                ErrorCorrection::Low => "low",
+                ErrorCorrection::Medium => "medium",
+                ErrorCorrection::Quartile => "quartile",
+                ErrorCorrection::High => "high",
+            };
+            f.write_str(name)
+        }
+    }
+
+    /// the ECI (Extended Channel Interpretation) assignment number for UTF-8
+    const ECI_UTF8: u32 = 26;
+
+    /// an encoded QR code: a square grid of dark and light modules, without the quiet zone
+    #[derive(Clone, PartialEq, Eq)]
+    pub struct QrMatrix {
+        code: QrCode,
+    }
+
+    impl QrMatrix {
+        /// encodes text at the given error correction level, in the smallest version that fits.
+        /// The level is raised if that doesn't make the code any larger
+        pub fn encode_text(
+            text: &str,
+            error_correction: ErrorCorrection,
+        ) -> Result<Self, Box<dyn error::Error>> {
+            let code = QrCode::encode_text(text, error_correction.to_qrcodegen())?;
+            Ok(QrMatrix { code })
+        }
+
+        /// encodes text as bytes behind an ECI 26 designator, which tells readers the bytes are
+        /// UTF-8. Without one, readers fall back to ISO-8859-1 or guess, and may show emoji or CJK
+        /// text as mojibake
+        pub fn encode_utf8(
+            text: &str,
+            error_correction: ErrorCorrection,
+        ) -> Result<Self, Box<dyn error::Error>> {
+            let segments = [
+                QrSegment::make_eci(ECI_UTF8),
+                QrSegment::make_bytes(text.as_bytes()),
+            ];
+            let code = QrCode::encode_segments(&segments, error_correction.to_qrcodegen())?;
+            Ok(QrMatrix { code })
+        }
+
+        pub(crate) fn from_qrcode(code: QrCode) -> Self {
+            QrMatrix { code }
+        }
+
+        /// the width and height in modules, from 21 (version 1) to 177 (version 40)
+        pub fn size(&self) -> i32 {
+            self.code.size()
+        }
+
+        /// whether the module at column x, row y is dark. (0, 0) is the top left corner, and
+        /// anything outside the grid is light, like the quiet zone
+        pub fn get_module(&self, x: i32, y: i32) -> bool {
+            self.code.get_module(x, y)
+        }
+
+        /// the coordinates (x, y) of every dark module, row by row from the top
+        pub fn dark_modules(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+            let size = self.size();
+            (0..size)
+                .flat_map(move |y| (0..size).map(move |x| (x, y)))
+                .filter(move |&(x, y)| self.get_module(x, y))
+        }
+
+        /// the version, from 1 to 40
+        pub fn version(&self) -> u8 {
+            self.code.version().value()
+        }
+
+        /// the error correction level the code was encoded with
+        pub fn error_correction(&self) -> ErrorCorrection {
+            ErrorCorrection::from_qrcodegen(self.code.error_correction_level())
+        }
+
+        /// the mask pattern applied to the data modules, from 0 to 7
+        pub fn mask(&self) -> u8 {
+            self.code.mask().value()
+        }
+    }
+
+    impl fmt::Debug for QrMatrix {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("QrMatrix")
+                .field("version", &self.version())
+                .field("error_correction", &self.error_correction())
+                .field("mask", &self.mask())
+                .field("size", &self.size())
+                .finish()
+        }
+    }
+
+    /// the machine-readable forms of the module grid
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum MatrixFormat {
+        /// a JSON array of rows, each an array of 0 and 1, one row per line
+        Json,
+        /// one line of `0` and `1` characters per row
+        Text,
+        /// a binary PBM (portable bitmap) image with one pixel per module
+        Pbm,
+    }
+
+    impl FromStr for MatrixFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "json" => Ok(MatrixFormat::Json),
+                "text" | "txt" => Ok(MatrixFormat::Text),
+                "pbm" => Ok(MatrixFormat::Pbm),
+                _ => Err(format!(
+                    "Unknown matrix format: {}. Try one of: json, text, pbm",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// returns the modules as rows of booleans (true for dark), with `border` light modules on
+    /// every side
+    pub fn module_matrix(qr: &QrMatrix, border: i32) -> Vec<Vec<bool>> {
+        let border = border.max(0);
+        (-border..qr.size() + border)
+            .map(|y| {
+                (-border..qr.size() + border)
+                    .map(|x| qr.get_module(x, y))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// writes the module grid with `border` light modules on every side
+    ///
+    /// * qr: an encoded QR code
+    ///
+    /// * border: the quiet zone, in modules
+    ///
+    /// * format: see MatrixFormat
+    ///
+    /// * out: where to write the grid
+    pub fn write_matrix(
+        qr: &QrMatrix,
+        border: i32,
+        format: MatrixFormat,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let matrix = module_matrix(qr, border);
+
+        match format {
+            MatrixFormat::Json => {
+                writeln!(out, "[")?;
+                for (y, row) in matrix.iter().enumerate() {
+                    let cells: Vec<&str> = row.iter().map(|&d| if d { "1" } else { "0" }).collect();
+                    let separator = if y + 1 < matrix.len() { "," } else { "" };
+                    writeln!(out, "  [{}]{}", cells.join(","), separator)?;
+                }
+                writeln!(out, "]")
+            }
+            MatrixFormat::Text => {
+                for row in matrix.iter() {
+                    let line: String = row.iter().map(|&d| if d { '1' } else { '0' }).collect();
+                    writeln!(out, "{}", line)?;
+                }
+                Ok(())
+            }
+            MatrixFormat::Pbm => {
+                let size = matrix.len() as u32;
+                // in PBM, 1 is black
+                let pixels: Vec<u8> = matrix.iter().flatten().map(|&dark| dark as u8).collect();
+                write!(out, "P4\n{0} {0}\n", size)?;
+                out.write_all(&pack_bits(size, &pixels))
+            }
+        }
+    }
+}
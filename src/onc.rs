@@ -0,0 +1,184 @@
+/// Import and export of ChromeOS ONC (Open Network Configuration) documents
+///
+/// Only the subset of ONC needed to describe a single Wi-Fi network is handled:
+/// `NetworkConfigurations` entries with `Type: WiFi`, and their `WiFi.SSID`, `WiFi.HexSSID`,
+/// `WiFi.Security`, `WiFi.Passphrase` and `WiFi.HiddenSSID` keys. Enterprise (`WPA-EAP`) networks
+/// are refused both ways: their EAP method, identity and certificates have no place in a `WIFI:`
+/// payload, so they could neither be exported faithfully nor imported as a network phones can join.
+///
+/// Format documentation:
+/// https://chromium.googlesource.com/chromium/src/+/main/components/onc/docs/onc_spec.md
+pub mod methods {
+    use serde_json::{json, Map, Value};
+
+    use crate::code::Credentials;
+    use crate::wifi::methods::{from_hex, to_hex};
+
+    /// Maps a `Credentials` encryption type onto an ONC `WiFi.Security` value
+    fn security_from_encr(encr: &str) -> Result<&'static str, OncError> {
+        match encr.to_uppercase().as_str() {
+            "" | "NOPASS" => Ok("None"),
+            "WEP" => Ok("WEP-PSK"),
+            "WPA" | "WPA2" | "WPA3" => Ok("WPA-PSK"),
+            "EAP" | "WPA-EAP" | "WPA2-EAP" | "WPA3-EAP" => Err(OncError(format!(
+                "The encryption type `{}` is an enterprise (WPA-EAP) network. ONC export needs its EAP method and identity, which the credentials don't carry",
+                encr
+            ))),
+            _ => Err(OncError(format!(
+                "The encryption type `{}` has no ONC equivalent. Try one of: nopass, wep, wpa, wpa2, wpa3",
+                encr
+            ))),
+        }
+    }
+
+    /// Maps an ONC `WiFi.Security` value onto a `Credentials` encryption type
+    fn encr_from_security(security: &str) -> Result<&'static str, OncError> {
+        match security {
+            "None" => Ok("nopass"),
+            "WEP-PSK" => Ok("WEP"),
+            "WPA-PSK" => Ok("WPA2"),
+            "WPA-EAP" => Err(OncError(
+                "The network uses WPA-EAP (enterprise) security, which a Wi-Fi QR code can't carry"
+                    .to_string(),
+            )),
+            _ => Err(OncError(format!(
+                "The ONC security type `{}` is not supported. Supported types are: None, WEP-PSK, WPA-PSK",
+                security
+            ))),
+        }
+    }
+
+    /// builds a single ONC `NetworkConfigurations` entry for a set of credentials
+    ///
+    /// * config: the credentials to convert. These are validated with `Credentials::format()` first,
+    ///   so that an ONC fragment is never produced for a network that could not also be encoded as a QR code.
+    pub fn to_network_configuration(config: &Credentials) -> Result<Value, OncError> {
+        if let Err(e) = config.format() {
+            return Err(OncError(e.to_string()));
+        }
+
+        let security = security_from_encr(&config.encr)?;
+        let hex_ssid = to_hex(config.ssid_bytes());
+
+        let mut wifi = Map::new();
+        wifi.insert("SSID".to_string(), json!(config.ssid));
+        wifi.insert("HexSSID".to_string(), json!(hex_ssid));
+        wifi.insert("Security".to_string(), json!(security));
+        wifi.insert("HiddenSSID".to_string(), json!(config.hidden));
+        wifi.insert("AutoConnect".to_string(), json!(true));
+
+        if security != "None" {
+            wifi.insert("Passphrase".to_string(), json!(config.pass));
+        }
+
+        Ok(json!({
+            "GUID": format!("{{wifiqr-{}}}", hex_ssid),
+            "Name": config.ssid,
+            "Type": "WiFi",
+            "WiFi": wifi,
+        }))
+    }
+
+    /// generates a complete ONC document (an `UnencryptedConfiguration`) containing the network
+    /// described by `config`, suitable for pushing through ChromeOS device or user policy
+    pub fn to_onc_string(config: &Credentials) -> Result<String, OncError> {
+        let document = json!({
+            "Type": "UnencryptedConfiguration",
+            "NetworkConfigurations": [to_network_configuration(config)?],
+        });
+
+        match serde_json::to_string_pretty(&document) {
+            Ok(s) => Ok(s),
+            Err(e) => Err(OncError(e.to_string())),
+        }
+    }
+
+    /// converts a single ONC `NetworkConfigurations` entry into `Credentials`
+    ///
+    /// `WiFi.HexSSID` takes precedence over `WiFi.SSID` when both are present, as in ChromeOS. A
+    /// HexSSID that is not valid UTF-8 is kept as the raw SSID.
+    pub fn from_network_configuration(network: &Value) -> Result<Credentials, OncError> {
+        if network["Type"] != "WiFi" {
+            return Err(OncError(
+                "The network configuration is not of type WiFi".to_string(),
+            ));
+        }
+
+        let wifi = &network["WiFi"];
+
+        let ssid = match (wifi["HexSSID"].as_str(), wifi["SSID"].as_str()) {
+            (Some(hex), _) => from_hex(hex).ok_or_else(|| {
+                OncError(format!("WiFi.HexSSID `{}` is not a valid hex string", hex))
+            })?,
+            (None, Some(ssid)) => ssid.as_bytes().to_vec(),
+            (None, None) => {
+                return Err(OncError(
+                    "The WiFi network configuration has neither an SSID nor a HexSSID".to_string(),
+                ))
+            }
+        };
+
+        let encr = encr_from_security(wifi["Security"].as_str().unwrap_or("None"))?;
+
+        let pass = wifi["Passphrase"].as_str().unwrap_or("");
+
+        let hidden = wifi["HiddenSSID"].as_bool().unwrap_or(false);
+
+        Ok(
+            Credentials::new(Some(""), Some(pass), Some(encr), hidden, false)
+                .with_ssid_bytes(&ssid),
+        )
+    }
+
+    /// parses an ONC document and returns `Credentials` for every Wi-Fi network it contains.
+    /// Entries with a `Type` other than `WiFi` (Ethernet, VPN, ...) are skipped.
+    ///
+    /// Both complete documents (with a top-level `NetworkConfigurations` array) and
+    /// a bare `NetworkConfigurations` entry are accepted.
+    pub fn from_onc_str(onc: &str) -> Result<Vec<Credentials>, OncError> {
+        let document: Value = match serde_json::from_str(onc) {
+            Ok(v) => v,
+            Err(e) => return Err(OncError(format!("Unable to parse ONC JSON: {}", e))),
+        };
+
+        if document["Type"] == "EncryptedConfiguration" {
+            return Err(OncError(
+                "Encrypted ONC documents are not supported. Export the configuration unencrypted."
+                    .to_string(),
+            ));
+        }
+
+        let networks = match document["NetworkConfigurations"].as_array() {
+            Some(n) => n.iter().collect(),
+            None if document["Type"] == "WiFi" => vec![&document],
+            None => {
+                return Err(OncError(
+                    "The ONC document has no NetworkConfigurations".to_string(),
+                ))
+            }
+        };
+
+        networks
+            .into_iter()
+            .filter(|n| n["Type"] == "WiFi")
+            .map(from_network_configuration)
+            .collect()
+    }
+
+    /// this error is returned when an ONC document cannot be read, or when credentials
+    /// cannot be represented as an ONC network configuration
+    #[derive(Debug, Clone)]
+    pub struct OncError(String);
+
+    impl std::error::Error for OncError {
+        fn description(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl std::fmt::Display for OncError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+}
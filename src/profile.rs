@@ -0,0 +1,127 @@
+/// Reader compatibility profiles for the `WIFI:` payload
+///
+/// Readers agree on the basic `WIFI:T:..;S:..;P:..;;` syntax, but differ in the details: the case
+/// of the encryption type, whether quotes around a value are stripped, which escapes are undone,
+/// and whether `H:false` is understood. A profile fixes each of these choices for a target reader:
+///
+/// | profile      | `T:` case | quote hex-looking values | escape `:` and `,` | `H:false` | field order |
+/// |--------------|-----------|--------------------------|--------------------|-----------|-------------|
+/// | `max-compat` | uppercase | only with `quote`        | no                 | no        | T, S, P, H  |
+/// | `ios`        | uppercase | never                    | no                 | no        | T, S, P, H  |
+/// | `android`    | as given  | yes                      | yes                | yes       | S, T, P, H  |
+/// | `zxing`      | uppercase | yes                      | yes                | no        | T, S, P, H  |
+/// | `strict`     | uppercase | yes                      | yes                | no        | T, S, P, H  |
+///
+/// `strict` also writes WPA2 and WPA3 as `WPA`, and rejects types other than WEP, WPA and nopass.
+/// The ZXing description also lists WPA2-EAP, but a payload can't carry the EAP settings such a
+/// network needs. The README lists the reader behavior each choice is based on.
+pub mod methods {
+    use alloc::format;
+    use alloc::string::String;
+    use core::fmt;
+    use core::str::FromStr;
+
+    /// a set of payload formatting rules, named after the reader it targets
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+    pub enum Profile {
+        /// only what every tested reader handles: no optional escapes, quotes or fields. The
+        /// default, and the format written before profiles existed
+        #[default]
+        MaxCompat,
+        /// the iOS Camera app, which needs an uppercase `T:` and keeps quotes as part of a value,
+        /// so values are never quoted, even with `quote` set
+        Ios,
+        /// the Android camera and Wi-Fi settings: the layout Android writes in its own sharing
+        /// codes, with the SSID first and `H:` always present
+        Android,
+        /// ZXing-based scanners and libraries: every special character escaped, and values that
+        /// could be read as hex quoted
+        Zxing,
+        /// the ZXing description of the format, followed to the letter
+        Strict,
+    }
+
+    /// the order in which a profile writes the payload fields
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum FieldOrder {
+        /// `T:`, `S:`, `P:`, `H:`, as in the ZXing examples
+        EncryptionFirst,
+        /// `S:`, `T:`, `P:`, `H:`, as Android writes them
+        SsidFirst,
+    }
+
+    impl Profile {
+        /// whether the encryption type is written in uppercase (`nopass` always stays lowercase)
+        pub fn uppercase_encryption(self) -> bool {
+            !matches!(self, Profile::Android)
+        }
+
+        /// whether Credentials::quote is honored
+        pub fn allows_quotes(self) -> bool {
+            !matches!(self, Profile::Ios)
+        }
+
+        /// whether SSIDs and passwords made only of hex digits are quoted, so readers don't decode
+        /// them as hex. Passwords with the length of a raw WEP or WPA key stay unquoted, since
+        /// access points read those as hex too
+        pub fn quotes_hex(self) -> bool {
+            matches!(self, Profile::Android | Profile::Zxing | Profile::Strict)
+        }
+
+        /// whether `:` and `,` are escaped with a backslash, besides `\`, `;` and `"`
+        pub fn escapes_separators(self) -> bool {
+            matches!(self, Profile::Android | Profile::Zxing | Profile::Strict)
+        }
+
+        /// whether visible networks get an explicit `H:false`
+        pub fn explicit_hidden(self) -> bool {
+            matches!(self, Profile::Android)
+        }
+
+        /// the order of the payload fields
+        pub fn field_order(self) -> FieldOrder {
+            match self {
+                Profile::Android => FieldOrder::SsidFirst,
+                _ => FieldOrder::EncryptionFirst,
+            }
+        }
+
+        /// whether encryption types are limited to WEP, WPA and nopass
+        pub fn strict_encryption(self) -> bool {
+            matches!(self, Profile::Strict)
+        }
+    }
+
+    impl FromStr for Profile {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "max-compat" | "maxcompat" | "default" => Ok(Profile::MaxCompat),
+                "ios" => Ok(Profile::Ios),
+                "android" => Ok(Profile::Android),
+                "zxing" => Ok(Profile::Zxing),
+                "strict" => Ok(Profile::Strict),
+                _ => Err(format!(
+                    "Unknown profile: {}. Try one of: ios, android, zxing, strict, max-compat",
+                    s
+                )),
+            }
+        }
+    }
+
+    impl fmt::Display for Profile {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                Profile::MaxCompat => "max-compat",
+                Profile::Ios => "ios",
+                Profile::Android => "android",
+                Profile::Zxing => "zxing",
+                Profile::Strict => "strict",
+            };
+            f.write_str(name)
+        }
+    }
+}
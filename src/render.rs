@@ -0,0 +1,1308 @@
+/// A common interface over every output format
+///
+/// Each format implements `Renderer`, takes the same `RenderOptions` and writes into any io::Write.
+/// Renderers are looked up by name or file extension through a `Registry`, which downstream crates
+/// can extend with their own formats.
+pub mod methods {
+    use std::error;
+    #[cfg(feature = "raster")]
+    use std::io::Cursor;
+    use std::io::Write;
+    use std::path::Path;
+    use std::str::FromStr;
+
+    #[cfg(feature = "raster")]
+    use image::codecs::{
+        bmp::BmpEncoder,
+        gif::GifEncoder,
+        ico::IcoEncoder,
+        jpeg::{JpegEncoder, PixelDensity},
+        pnm::{PnmEncoder, PnmSubtype, SampleEncoding},
+        tiff::TiffEncoder,
+        webp::WebPEncoder,
+    };
+    #[cfg(feature = "raster")]
+    use image::{ColorType, ImageBuffer, ImageEncoder, LumaA, Rgba};
+
+    use crate::bitmap::methods::{write_c_header, write_xbm, BitmapLayout, BitmapOptions};
+    use crate::exporters::methods::{write_console, ConsoleOptions, ConsoleStyle};
+    use crate::matrix::methods::QrMatrix;
+    use crate::style::methods::{PathMode, SvgStyle};
+    #[cfg(feature = "svg")]
+    use crate::svg::methods::{write_svg_with_options, SvgOptions};
+    #[cfg(feature = "raster")]
+    use crate::terminal::methods::{to_iterm_string, to_kitty_string, to_sixel_string};
+
+    /// an RGB color, used for the dark and light modules of a QR code
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Color {
+        pub r: u8,
+        pub g: u8,
+        pub b: u8,
+    }
+
+    impl Color {
+        pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+        pub const WHITE: Color = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+
+        /// returns the color as an uppercase `#RRGGBB` string, as used in SVG and HTML
+        pub fn to_hex(self) -> String {
+            format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+        }
+    }
+
+    impl FromStr for Color {
+        type Err = String;
+
+        /// parses `#RRGGBB` or `RRGGBB`
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let hex = s.trim_start_matches('#');
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(|| format!("Invalid color: {}. Try a value like #1A2B3C", s))
+            };
+
+            if hex.len() != 6 {
+                return Err(format!("Invalid color: {}. Try a value like #1A2B3C", s));
+            }
+
+            Ok(Color {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            })
+        }
+    }
+
+    /// the resolution used to convert physical sizes into pixels when no DPI is given, as in CSS
+    pub const DEFAULT_DPI: u32 = 96;
+
+    /// printed modules narrower than this many printer dots blur into each other
+    pub const MIN_MODULE_DOTS: u32 = 4;
+
+    /// the largest width and height of raster output, in pixels. A 16384 pixel square image is
+    /// already 1 GiB of RGBA pixels; larger sizes are refused instead of exhausting memory
+    pub const MAX_RASTER_SIZE: u32 = 16384;
+
+    /// a unit for physical output sizes
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Unit {
+        Px,
+        Mm,
+        Cm,
+        In,
+        Pt,
+    }
+
+    impl Unit {
+        /// the suffix used for this unit in SVG and CSS
+        pub fn suffix(self) -> &'static str {
+            match self {
+                Unit::Px => "px",
+                Unit::Mm => "mm",
+                Unit::Cm => "cm",
+                Unit::In => "in",
+                Unit::Pt => "pt",
+            }
+        }
+    }
+
+    /// a physical length, e.g. the printed width of a code
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Length {
+        pub value: f64,
+        pub unit: Unit,
+    }
+
+    impl Length {
+        /// converts the length into (fractional) pixels at a resolution, in dots per inch
+        pub fn to_pixels(self, dpi: u32) -> f64 {
+            let dpi = f64::from(dpi);
+            match self.unit {
+                Unit::Px => self.value,
+                Unit::Mm => self.value / 25.4 * dpi,
+                Unit::Cm => self.value / 2.54 * dpi,
+                Unit::In => self.value * dpi,
+                Unit::Pt => self.value / 72.0 * dpi,
+            }
+        }
+    }
+
+    impl std::fmt::Display for Length {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}{}", self.value, self.unit.suffix())
+        }
+    }
+
+    impl FromStr for Length {
+        type Err = String;
+
+        /// parses a positive number with an optional unit, e.g. `40mm`, `1.5 in` or `300` (pixels)
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || format!("Invalid size: {}. Try a value like 40mm, 1.5in or 300px", s);
+
+            let s = s.trim();
+            let split = s
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(s.len());
+            let (number, unit) = s.split_at(split);
+
+            let unit = match unit.trim().to_lowercase().as_str() {
+                "" | "px" => Unit::Px,
+                "mm" => Unit::Mm,
+                "cm" => Unit::Cm,
+                "in" => Unit::In,
+                "pt" => Unit::Pt,
+                _ => return Err(invalid()),
+            };
+
+            match number.parse::<f64>() {
+                Ok(value) if value > 0.0 && value.is_finite() => Ok(Length { value, unit }),
+                _ => Err(invalid()),
+            }
+        }
+    }
+
+    /// how PNG output is encoded. A QR code only has two colors, so the 1-bit modes are far smaller
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum PngMode {
+        /// 8-bit RGBA
+        Rgba,
+        /// 1-bit grayscale. Only black and white codes can be written this way
+        Gray,
+        /// 1-bit, with a 2-entry palette of the dark and light colors
+        Palette,
+    }
+
+    impl FromStr for PngMode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "rgba" => Ok(PngMode::Rgba),
+                "gray" | "grey" => Ok(PngMode::Gray),
+                "palette" => Ok(PngMode::Palette),
+                _ => Err(format!(
+                    "Unknown PNG mode: {}. Try one of: palette, gray, rgba",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// options shared by every renderer. Renderers ignore the options that do not apply to them
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RenderOptions {
+        /// the size of the quiet zone (border) around the code, in modules
+        pub quiet_zone: i32,
+        /// the size of a single module, in pixels, for raster and inline image output
+        pub module_size: i32,
+        /// the color of dark modules (svg and raster output)
+        pub dark_color: Color,
+        /// the color of light modules and the quiet zone (svg and raster output)
+        pub light_color: Color,
+        /// the block characters used for console output
+        pub style: ConsoleStyle,
+        /// swap dark and light modules
+        pub invert: bool,
+        /// use ANSI colors for console output
+        pub ansi_colors: bool,
+        /// module shapes, finder shapes and gradients for svg output
+        pub svg_style: SvgStyle,
+        /// how dark modules are written into svg output
+        pub svg_paths: PathMode,
+        /// the physical width and height of the output. Raster output picks the largest module size
+        /// that fits, instead of using module_size
+        pub size: Option<Length>,
+        /// the resolution of raster output, in dots per inch. It converts `size` into pixels and is
+        /// written into PNG (pHYs) and JPEG (JFIF) metadata, so print dialogs don't rescale the code
+        pub dpi: Option<u32>,
+        /// warn when printed modules (output with a dpi) are narrower than this many dots
+        pub min_module_dots: u32,
+        /// how png output is encoded
+        pub png_mode: PngMode,
+        /// make the light modules and the quiet zone of png output transparent
+        pub transparent: bool,
+        /// a text alternative for svg output, e.g. from Credentials::accessible_name()
+        pub title: Option<String>,
+        /// a longer description for svg output, e.g. from Credentials::accessible_description()
+        pub description: Option<String>,
+        /// the order of pixels in C header output
+        pub bitmap_layout: BitmapLayout,
+        /// the C identifier used by C header and XBM output. Defaults to `wifi_qr`
+        pub bitmap_name: Option<String>,
+    }
+
+    impl Default for RenderOptions {
+        fn default() -> Self {
+            RenderOptions {
+                quiet_zone: 4,
+                module_size: 10,
+                dark_color: Color::BLACK,
+                light_color: Color::WHITE,
+                style: ConsoleStyle::Full,
+                invert: false,
+                ansi_colors: false,
+                svg_style: SvgStyle::default(),
+                svg_paths: PathMode::Outlines,
+                size: None,
+                dpi: None,
+                min_module_dots: MIN_MODULE_DOTS,
+                png_mode: PngMode::Palette,
+                transparent: false,
+                title: None,
+                description: None,
+                bitmap_layout: BitmapLayout::Rows,
+                bitmap_name: None,
+            }
+        }
+    }
+
+    impl RenderOptions {
+        /// the options that apply to block-character console output
+        pub fn console_options(&self) -> ConsoleOptions {
+            ConsoleOptions {
+                quiet_zone: self.quiet_zone,
+                style: self.style,
+                invert: self.invert,
+                ansi_colors: self.ansi_colors,
+            }
+        }
+
+        /// the options that apply to svg output
+        #[cfg(feature = "svg")]
+        pub fn svg_options(&self) -> SvgOptions {
+            let (dark, light) = self.colors();
+
+            SvgOptions {
+                border: self.quiet_zone,
+                width: self.size,
+                height: self.size,
+                crisp_edges: true,
+                dark,
+                light,
+                style: self.svg_style,
+                paths: self.svg_paths,
+                title: self.title.clone(),
+                description: self.description.clone(),
+            }
+        }
+
+        /// the options that apply to C header and XBM output. Inverting sets the bits of light
+        /// pixels, for displays where 1 is white
+        pub fn bitmap_options(&self) -> BitmapOptions {
+            let defaults = BitmapOptions::default();
+
+            BitmapOptions {
+                layout: self.bitmap_layout,
+                invert: self.invert,
+                name: self.bitmap_name.clone().unwrap_or(defaults.name),
+            }
+        }
+
+        /// works out the image size, module size and quiet zone of raster output. Fails when the
+        /// image would be larger than MAX_RASTER_SIZE, or too small to fit the code
+        pub fn raster_layout(
+            &self,
+            qrcode: &QrMatrix,
+        ) -> Result<RasterLayout, Box<dyn error::Error>> {
+            let quiet_zone = self.quiet_zone.max(0) as u32;
+            let too_large = |pixels: String| {
+                format!(
+                    "The image would be {} pixels wide, but raster output is limited to {} pixels. Use a smaller scale, size, DPI or quiet zone.",
+                    pixels, MAX_RASTER_SIZE
+                )
+            };
+            let modules = match quiet_zone
+                .checked_mul(2)
+                .and_then(|border| border.checked_add(qrcode.size() as u32))
+            {
+                Some(modules) => modules,
+                None => return Err(too_large("more than 4 billion".to_string()).into()),
+            };
+
+            let size = match self.size {
+                Some(size) => size,
+                None => {
+                    let module_size = self.module_size.max(1) as u32;
+                    let pixels = match modules.checked_mul(module_size) {
+                        Some(pixels) if pixels <= MAX_RASTER_SIZE => pixels,
+                        Some(pixels) => return Err(too_large(pixels.to_string()).into()),
+                        None => return Err(too_large("more than 4 billion".to_string()).into()),
+                    };
+                    return Ok(RasterLayout {
+                        size: pixels,
+                        module_size,
+                        offset: quiet_zone * module_size,
+                    });
+                }
+            };
+
+            let dpi = self.dpi.unwrap_or(DEFAULT_DPI);
+            let exact = size.to_pixels(dpi).round();
+            if exact.is_nan() || exact > MAX_RASTER_SIZE as f64 {
+                return Err(too_large(format!("{}", exact)).into());
+            }
+            let pixels = exact as u32;
+            let module_size = pixels / modules;
+
+            if module_size == 0 {
+                let requested = match size.unit {
+                    Unit::Px => size.to_string(),
+                    _ => format!("{} at {} DPI ({} pixels)", size, dpi, pixels),
+                };
+                return Err(format!(
+                    "{} is too small: this code needs at least {} pixels ({} modules, quiet zone included). Use a larger size or a higher DPI.",
+                    requested, modules, modules
+                )
+                .into());
+            }
+
+            // pixels left over by rounding down the module size widen the quiet zone
+            let leftover = pixels - module_size * modules;
+            Ok(RasterLayout {
+                size: pixels,
+                module_size,
+                offset: quiet_zone * module_size + leftover / 2,
+            })
+        }
+
+        /// returns warnings about raster output that may not scan reliably, e.g. modules that are
+        /// too small to be printed crisply at the requested DPI
+        pub fn warnings(&self, qrcode: &QrMatrix) -> Vec<String> {
+            let mut warnings = Vec::new();
+
+            if let (Some(dpi), Ok(layout)) = (self.dpi, self.raster_layout(qrcode)) {
+                if layout.module_size < self.min_module_dots {
+                    warnings.push(format!(
+                        "Modules are {} dots wide at {} DPI. Printers need at least {} dots per module for a reliable scan; use a larger size or a higher DPI.",
+                        layout.module_size, dpi, self.min_module_dots
+                    ));
+                }
+            }
+
+            warnings
+        }
+
+        /// the dark and light colors, after applying `invert`
+        pub fn colors(&self) -> (Color, Color) {
+            if self.invert {
+                (self.light_color, self.dark_color)
+            } else {
+                (self.dark_color, self.light_color)
+            }
+        }
+    }
+
+    /// where a QR code is drawn on a square raster image, in pixels
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RasterLayout {
+        /// the width and height of the image
+        pub size: u32,
+        /// the width and height of a module
+        pub module_size: u32,
+        /// the distance from the top and left edges to the first module
+        pub offset: u32,
+    }
+
+    impl RasterLayout {
+        /// whether the pixel at x, y is part of a dark module
+        pub fn is_dark(&self, qrcode: &QrMatrix, x: u32, y: u32) -> bool {
+            if x < self.offset || y < self.offset {
+                return false;
+            }
+
+            // get_module() treats coordinates outside the symbol as light
+            let module = |p: u32| ((p - self.offset) / self.module_size) as i32;
+            qrcode.get_module(module(x), module(y))
+        }
+
+        /// draws a QR code into a new pixel buffer, row by row
+        ///
+        /// * dark, light: the bytes of a single dark and light pixel, e.g. `[0, 255]` for LumaA
+        ///
+        /// Each row of modules is drawn once, then copied for every pixel row it covers.
+        pub(crate) fn fill(&self, qrcode: &QrMatrix, dark: &[u8], light: &[u8]) -> Vec<u8> {
+            let channels = dark.len();
+            let size = self.size as usize;
+            let module_size = self.module_size as usize;
+            let offset = self.offset as usize;
+
+            let light_row: Vec<u8> = light
+                .iter()
+                .copied()
+                .cycle()
+                .take(size * channels)
+                .collect();
+            let mut buffer = Vec::with_capacity(size * size * channels);
+
+            // the quiet zone above the symbol
+            for _ in 0..offset.min(size) {
+                buffer.extend_from_slice(&light_row);
+            }
+
+            let mut row = light_row.clone();
+            for y in 0..qrcode.size() {
+                if buffer.len() >= size * size * channels {
+                    break;
+                }
+
+                row.copy_from_slice(&light_row);
+                for x in 0..qrcode.size() {
+                    if qrcode.get_module(x, y) {
+                        let start = (offset + x as usize * module_size).min(size);
+                        let end = (start + module_size).min(size);
+                        for pixel in
+                            row[start * channels..end * channels].chunks_exact_mut(channels)
+                        {
+                            pixel.copy_from_slice(dark);
+                        }
+                    }
+                }
+
+                for _ in 0..module_size {
+                    if buffer.len() >= size * size * channels {
+                        break;
+                    }
+                    buffer.extend_from_slice(&row);
+                }
+            }
+
+            // the quiet zone below the symbol, including any pixels left over by the layout
+            while buffer.len() < size * size * channels {
+                buffer.extend_from_slice(&light_row);
+            }
+
+            buffer
+        }
+    }
+
+    /// an output format for QR codes
+    ///
+    /// Implement this to add a format, then add it to a `Registry` with `Registry::register()`.
+    pub trait Renderer {
+        /// the name used to select this renderer, e.g. `svg`
+        fn name(&self) -> &str;
+
+        /// the file extensions (without a dot, lowercase) this renderer is chosen for
+        fn extensions(&self) -> &[&str];
+
+        /// the MIME type of this renderer's output, used for `data:` URIs
+        fn mime_type(&self) -> &str {
+            "application/octet-stream"
+        }
+
+        /// writes the QR code, in this renderer's format, into `out`
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>>;
+    }
+
+    /// returns a `data:` URI with base64 encoded content, for embedding output in HTML, CSS or email
+    ///
+    /// * mime: the MIME type of the data, e.g. `image/png`
+    pub fn data_uri(mime: &str, data: &[u8]) -> String {
+        format!("data:{};base64,{}", mime, base64::encode(data))
+    }
+
+    /// rasterizes a QR code with the colors and size from `options`
+    #[cfg(feature = "raster")]
+    pub(crate) fn raster(
+        qrcode: &QrMatrix,
+        options: &RenderOptions,
+    ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, Box<dyn error::Error>> {
+        let layout = options.raster_layout(qrcode)?;
+        let (dark, light) = options.colors();
+
+        let light_alpha = if options.transparent { 0 } else { 255 };
+
+        let buffer = layout.fill(
+            qrcode,
+            &[dark.r, dark.g, dark.b, 255],
+            &[light.r, light.g, light.b, light_alpha],
+        );
+        Ok(ImageBuffer::from_raw(layout.size, layout.size, buffer).unwrap())
+    }
+
+    /// the payload of a PNG pHYs chunk: pixels per meter on both axes, and the unit (1 = meter)
+    #[cfg(feature = "raster")]
+    fn phys_chunk(dpi: u32) -> [u8; 9] {
+        let pixels_per_meter = (f64::from(dpi) / 0.0254).round() as u32;
+        let mut chunk = [1u8; 9];
+        chunk[0..4].copy_from_slice(&pixels_per_meter.to_be_bytes());
+        chunk[4..8].copy_from_slice(&pixels_per_meter.to_be_bytes());
+        chunk
+    }
+
+    /// the 1-bit gray level of a color, for grayscale PNG output
+    #[cfg(feature = "raster")]
+    fn gray_level(color: Color) -> Result<u8, String> {
+        match color {
+            Color::BLACK => Ok(0),
+            Color::WHITE => Ok(1),
+            _ => Err(format!(
+                "1-bit grayscale PNGs can only be black and white, not {}. Use the palette PNG mode for colors.",
+                color.to_hex()
+            )),
+        }
+    }
+
+    /// packs pixels of one byte each (0 or 1) into rows of 1-bit samples, most significant bit first.
+    /// Each row starts on a new byte, as PNG requires
+    pub(crate) fn pack_bits(width: u32, pixels: &[u8]) -> Vec<u8> {
+        pixels
+            .chunks(width as usize)
+            .flat_map(|row| {
+                row.chunks(8).map(|bits| {
+                    bits.iter()
+                        .enumerate()
+                        .fold(0u8, |byte, (i, &bit)| byte | (bit << (7 - i)))
+                })
+            })
+            .collect()
+    }
+
+    /// rasterizes a QR code in black and white, for output that is always scanned from a screen.
+    /// The terminal protocols draw two gray levels, so other colors are an error
+    ///
+    /// * protocol: the renderer name, for the error message
+    #[cfg(feature = "raster")]
+    fn raster_bw(
+        qrcode: &QrMatrix,
+        options: &RenderOptions,
+        protocol: &str,
+    ) -> Result<ImageBuffer<LumaA<u8>, Vec<u8>>, Box<dyn error::Error>> {
+        let layout = options.raster_layout(qrcode)?;
+        let (dark, light) = options.colors();
+        let gray = |color: Color| match color {
+            Color::BLACK => Ok(0),
+            Color::WHITE => Ok(255),
+            _ => Err(format!(
+                "{} output can only be black and white, not {}. Use another format for colors.",
+                protocol,
+                color.to_hex()
+            )),
+        };
+
+        let buffer = layout.fill(qrcode, &[gray(dark)?, 255], &[gray(light)?, 255]);
+        Ok(ImageBuffer::from_raw(layout.size, layout.size, buffer).unwrap())
+    }
+
+    /// SVG documents
+    #[cfg(feature = "svg")]
+    pub struct SvgRenderer;
+
+    #[cfg(feature = "svg")]
+    impl Renderer for SvgRenderer {
+        fn name(&self) -> &str {
+            "svg"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["svg"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/svg+xml"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            // contrast is checked before inverting, since an explicit invert is on purpose
+            options
+                .svg_style
+                .check(options.dark_color, options.light_color)?;
+
+            write_svg_with_options(qrcode, &options.svg_options(), out)
+        }
+    }
+
+    /// PNG images
+    #[cfg(feature = "raster")]
+    pub struct PngRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for PngRenderer {
+        fn name(&self) -> &str {
+            "png"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["png"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/png"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            if options.png_mode == PngMode::Rgba {
+                let image = raster(qrcode, options)?;
+
+                let mut encoder = png::Encoder::new(out, image.width(), image.height());
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_compression(png::Compression::Best);
+
+                let mut writer = encoder.write_header()?;
+                // pHYs must come before the image data
+                if let Some(dpi) = options.dpi {
+                    writer.write_chunk(png::chunk::pHYs, &phys_chunk(dpi))?;
+                }
+                writer.write_image_data(image.as_raw())?;
+                return Ok(());
+            }
+
+            let layout = options.raster_layout(qrcode)?;
+            let (dark, light) = options.colors();
+
+            let mut encoder = png::Encoder::new(out, layout.size, layout.size);
+            // the default level does poorly on large flat areas, and codes are cheap to compress
+            encoder.set_compression(png::Compression::Best);
+            encoder.set_depth(png::BitDepth::One);
+
+            // a pixel is 0 or 1: a gray level (black or white), or a palette index
+            let (dark_bit, light_bit) = match options.png_mode {
+                PngMode::Gray => {
+                    encoder.set_color(png::ColorType::Grayscale);
+                    if options.transparent {
+                        // the gray level that is transparent, as a 16-bit sample
+                        encoder.set_trns(vec![0, gray_level(light)?]);
+                    }
+                    (gray_level(dark)?, gray_level(light)?)
+                }
+                _ => {
+                    encoder.set_color(png::ColorType::Indexed);
+                    encoder.set_palette(vec![dark.r, dark.g, dark.b, light.r, light.g, light.b]);
+                    if options.transparent {
+                        // alpha for each palette entry
+                        encoder.set_trns(vec![255, 0]);
+                    }
+                    (0, 1)
+                }
+            };
+
+            let pixels = layout.fill(qrcode, &[dark_bit], &[light_bit]);
+
+            let mut writer = encoder.write_header()?;
+            if let Some(dpi) = options.dpi {
+                writer.write_chunk(png::chunk::pHYs, &phys_chunk(dpi))?;
+            }
+            writer.write_image_data(&pack_bits(layout.size, &pixels))?;
+            Ok(())
+        }
+    }
+
+    /// JPEG images
+    #[cfg(feature = "raster")]
+    pub struct JpegRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for JpegRenderer {
+        fn name(&self) -> &str {
+            "jpeg"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["jpeg", "jpg"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/jpeg"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster(qrcode, options)?;
+            // JpegEncoder borrows its writer, and needs a sized one
+            let mut out = out;
+            let mut encoder = JpegEncoder::new(&mut out);
+            if let Some(dpi) = options.dpi {
+                // JFIF stores the density in 16 bits
+                encoder.set_pixel_density(PixelDensity::dpi(dpi.min(u32::from(u16::MAX)) as u16));
+            }
+            encoder.encode(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+            Ok(())
+        }
+    }
+
+    /// BMP images
+    #[cfg(feature = "raster")]
+    pub struct BmpRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for BmpRenderer {
+        fn name(&self) -> &str {
+            "bmp"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["bmp"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/bmp"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster(qrcode, options)?;
+            let mut out = out;
+            BmpEncoder::new(&mut out).write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+            Ok(())
+        }
+    }
+
+    /// GIF images. Codes have two colors, so the palette is exact
+    #[cfg(feature = "raster")]
+    pub struct GifRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for GifRenderer {
+        fn name(&self) -> &str {
+            "gif"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["gif"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/gif"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster(qrcode, options)?;
+            GifEncoder::new(out).encode(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+            Ok(())
+        }
+    }
+
+    /// lossless WebP images
+    #[cfg(feature = "raster")]
+    pub struct WebpRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for WebpRenderer {
+        fn name(&self) -> &str {
+            "webp"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["webp"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/webp"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster(qrcode, options)?;
+            WebPEncoder::new_lossless(out).write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+            Ok(())
+        }
+    }
+
+    /// TIFF images
+    #[cfg(feature = "raster")]
+    pub struct TiffRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for TiffRenderer {
+        fn name(&self) -> &str {
+            "tiff"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["tiff", "tif"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/tiff"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster(qrcode, options)?;
+            // TIFF offsets are patched in after the image data, so the encoder needs to seek
+            let mut buffer = Cursor::new(Vec::new());
+            TiffEncoder::new(&mut buffer).write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+            out.write_all(buffer.get_ref())?;
+            Ok(())
+        }
+    }
+
+    /// ICO icons, which are at most 256 pixels square. Without a size in the options, larger codes
+    /// are scaled down to fit
+    #[cfg(feature = "raster")]
+    pub struct IcoRenderer;
+
+    /// the largest width and height of an ICO image
+    #[cfg(feature = "raster")]
+    const ICO_MAX_SIZE: u32 = 256;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for IcoRenderer {
+        fn name(&self) -> &str {
+            "ico"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["ico"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/vnd.microsoft.icon"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let layout = options.raster_layout(qrcode)?;
+            let image = if layout.size <= ICO_MAX_SIZE {
+                raster(qrcode, options)?
+            } else if options.size.is_none() {
+                let fitted = RenderOptions {
+                    size: Some(Length {
+                        value: f64::from(ICO_MAX_SIZE),
+                        unit: Unit::Px,
+                    }),
+                    dpi: None,
+                    ..options.clone()
+                };
+                raster(qrcode, &fitted)?
+            } else {
+                return Err(format!(
+                    "ICO images can be at most {0}x{0} pixels, but this code is {1}x{1}. Try a size of {0}px or less.",
+                    ICO_MAX_SIZE, layout.size
+                )
+                .into());
+            };
+
+            IcoEncoder::new(out).write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                ColorType::Rgba8,
+            )?;
+            Ok(())
+        }
+    }
+
+    /// binary PBM (portable bitmap) images, with one bit per pixel. Dark modules are black, so the
+    /// colors must be black on white
+    pub struct PbmRenderer;
+
+    impl Renderer for PbmRenderer {
+        fn name(&self) -> &str {
+            "pbm"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["pbm"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/x-portable-bitmap"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let (dark, light) = options.colors();
+            if (dark, light) != (Color::BLACK, Color::WHITE) {
+                return Err(format!(
+                    "PBM images can only be black on white, not {} on {}. Use the pnm format for colors.",
+                    dark.to_hex(),
+                    light.to_hex()
+                )
+                .into());
+            }
+
+            let layout = options.raster_layout(qrcode)?;
+            // in PBM, 1 is black
+            let pixels = layout.fill(qrcode, &[1], &[0]);
+            write!(out, "P4\n{0} {0}\n", layout.size)?;
+            out.write_all(&pack_bits(layout.size, &pixels))?;
+            Ok(())
+        }
+    }
+
+    /// binary PPM (portable pixmap) images, for the pnm and ppm extensions. PPM has no alpha
+    /// channel, so transparency is ignored
+    #[cfg(feature = "raster")]
+    pub struct PnmRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for PnmRenderer {
+        fn name(&self) -> &str {
+            "pnm"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["pnm", "ppm"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/x-portable-pixmap"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let layout = options.raster_layout(qrcode)?;
+            let (dark, light) = options.colors();
+            let pixels = layout.fill(
+                qrcode,
+                &[dark.r, dark.g, dark.b],
+                &[light.r, light.g, light.b],
+            );
+            PnmEncoder::new(out)
+                .with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary))
+                .write_image(&pixels, layout.size, layout.size, ColorType::Rgb8)?;
+            Ok(())
+        }
+    }
+
+    /// C headers with a packed 1-bit bitmap, for microcontroller firmware
+    pub struct CHeaderRenderer;
+
+    impl Renderer for CHeaderRenderer {
+        fn name(&self) -> &str {
+            "c"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["h"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "text/x-c"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let layout = options.raster_layout(qrcode)?;
+            write_c_header(qrcode, &layout, &options.bitmap_options(), out)?;
+            Ok(())
+        }
+    }
+
+    /// XBM (X BitMap) images
+    pub struct XbmRenderer;
+
+    impl Renderer for XbmRenderer {
+        fn name(&self) -> &str {
+            "xbm"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["xbm"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "image/x-xbitmap"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let layout = options.raster_layout(qrcode)?;
+            write_xbm(qrcode, &layout, &options.bitmap_options(), out)?;
+            Ok(())
+        }
+    }
+
+    /// block characters for the console
+    pub struct ConsoleRenderer;
+
+    impl Renderer for ConsoleRenderer {
+        fn name(&self) -> &str {
+            "console"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+
+        fn mime_type(&self) -> &str {
+            "text/plain;charset=utf-8"
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            write_console(qrcode, &options.console_options(), out)?;
+            Ok(())
+        }
+    }
+
+    /// DEC Sixel inline images
+    #[cfg(feature = "raster")]
+    pub struct SixelRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for SixelRenderer {
+        fn name(&self) -> &str {
+            "sixel"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["six", "sixel"]
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster_bw(qrcode, options, "Sixel")?;
+            writeln!(out, "{}", to_sixel_string(&image))?;
+            Ok(())
+        }
+    }
+
+    /// kitty graphics protocol inline images
+    #[cfg(feature = "raster")]
+    pub struct KittyRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for KittyRenderer {
+        fn name(&self) -> &str {
+            "kitty"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &[]
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster_bw(qrcode, options, "kitty")?;
+            writeln!(out, "{}", to_kitty_string(&image)?)?;
+            Ok(())
+        }
+    }
+
+    /// iTerm2 inline images
+    #[cfg(feature = "raster")]
+    pub struct ItermRenderer;
+
+    #[cfg(feature = "raster")]
+    impl Renderer for ItermRenderer {
+        fn name(&self) -> &str {
+            "iterm"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &[]
+        }
+
+        fn render(
+            &self,
+            qrcode: &QrMatrix,
+            options: &RenderOptions,
+            out: &mut dyn Write,
+        ) -> Result<(), Box<dyn error::Error>> {
+            let image = raster_bw(qrcode, options, "iTerm2")?;
+            writeln!(out, "{}", to_iterm_string(&image)?)?;
+            Ok(())
+        }
+    }
+
+    /// a set of renderers, looked up by name or by file extension
+    ///
+    /// `Registry::default()` holds every built-in format. Renderers registered later take precedence
+    /// over earlier ones with the same name or extension, so built-ins can be replaced.
+    pub struct Registry {
+        renderers: Vec<Box<dyn Renderer>>,
+    }
+
+    impl Registry {
+        /// returns an empty registry
+        pub fn new() -> Self {
+            Registry {
+                renderers: Vec::new(),
+            }
+        }
+
+        /// adds a renderer to the registry
+        pub fn register(&mut self, renderer: Box<dyn Renderer>) {
+            self.renderers.push(renderer);
+        }
+
+        /// finds a renderer by name (case insensitive)
+        pub fn get(&self, name: &str) -> Option<&dyn Renderer> {
+            self.renderers
+                .iter()
+                .rev()
+                .find(|r| r.name().eq_ignore_ascii_case(name))
+                .map(|r| r.as_ref())
+        }
+
+        /// finds a renderer by file extension (case insensitive, without the dot)
+        pub fn for_extension(&self, extension: &str) -> Option<&dyn Renderer> {
+            let extension = extension.to_lowercase();
+            self.renderers
+                .iter()
+                .rev()
+                .find(|r| r.extensions().contains(&extension.as_str()))
+                .map(|r| r.as_ref())
+        }
+
+        /// finds a renderer for a file path, using its extension
+        pub fn for_path<P: AsRef<Path>>(&self, path: P) -> Option<&dyn Renderer> {
+            path.as_ref()
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(|e| self.for_extension(e))
+        }
+
+        /// the names of all registered renderers, in registration order and without duplicates
+        pub fn names(&self) -> Vec<&str> {
+            let mut names: Vec<&str> = Vec::new();
+            for r in self.renderers.iter() {
+                if !names.contains(&r.name()) {
+                    names.push(r.name());
+                }
+            }
+            names
+        }
+
+        /// the file extensions of all registered renderers, without duplicates
+        pub fn extensions(&self) -> Vec<&str> {
+            let mut extensions: Vec<&str> = Vec::new();
+            for e in self.renderers.iter().flat_map(|r| r.extensions().iter()) {
+                if !extensions.contains(e) {
+                    extensions.push(e);
+                }
+            }
+            extensions
+        }
+    }
+
+    impl Default for Registry {
+        fn default() -> Self {
+            let mut registry = Registry::new();
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(PngRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(JpegRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(WebpRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(GifRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(BmpRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(TiffRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(IcoRenderer));
+            registry.register(Box::new(PbmRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(PnmRenderer));
+            registry.register(Box::new(CHeaderRenderer));
+            registry.register(Box::new(XbmRenderer));
+            #[cfg(feature = "svg")]
+            registry.register(Box::new(SvgRenderer));
+            registry.register(Box::new(ConsoleRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(SixelRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(KittyRenderer));
+            #[cfg(feature = "raster")]
+            registry.register(Box::new(ItermRenderer));
+            registry
+        }
+    }
+}
@@ -0,0 +1,123 @@
+/// The look of SVG output: module and finder pattern shapes, gradients, finder colors and path
+/// optimization
+///
+/// These are plain settings, drawn by the `svg` module. They are built whenever `std` is, so that
+/// RenderOptions has the same fields whichever output features are enabled.
+pub mod methods {
+    use std::str::FromStr;
+
+    use crate::render::methods::Color;
+
+    /// the shape drawn for each dark data module
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ModuleShape {
+        Square,
+        Rounded,
+        Dot,
+    }
+
+    impl FromStr for ModuleShape {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "square" => Ok(ModuleShape::Square),
+                "rounded" => Ok(ModuleShape::Rounded),
+                "dot" | "dots" => Ok(ModuleShape::Dot),
+                _ => Err(format!(
+                    "Unknown module shape: {}. Try one of: square, rounded, dot",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// the shape drawn for the three finder patterns ("eyes") in the corners
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum FinderShape {
+        Square,
+        Rounded,
+        Circle,
+    }
+
+    impl FromStr for FinderShape {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "square" => Ok(FinderShape::Square),
+                "rounded" => Ok(FinderShape::Rounded),
+                "circle" => Ok(FinderShape::Circle),
+                _ => Err(format!(
+                    "Unknown finder shape: {}. Try one of: square, rounded, circle",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// a gradient used to fill the dark modules, from the dark color to `to`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Gradient {
+        /// top-left to bottom-right
+        Linear { to: Color },
+        /// center to corners
+        Radial { to: Color },
+    }
+
+    /// the look of a styled SVG. The default style draws a plain code.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SvgStyle {
+        /// the shape of dark data modules
+        pub module_shape: ModuleShape,
+        /// the shape of the finder patterns
+        pub finder_shape: FinderShape,
+        /// fill the dark data modules with a gradient instead of a solid color
+        pub gradient: Option<Gradient>,
+        /// the color of the finder patterns' outer rings (defaults to the dark color)
+        pub finder_color: Option<Color>,
+        /// the color of the finder patterns' centers (defaults to the finder color)
+        pub finder_center_color: Option<Color>,
+    }
+
+    impl Default for SvgStyle {
+        fn default() -> Self {
+            SvgStyle {
+                module_shape: ModuleShape::Square,
+                finder_shape: FinderShape::Square,
+                gradient: None,
+                finder_color: None,
+                finder_center_color: None,
+            }
+        }
+    }
+
+    /// how square dark modules are written into the SVG path data
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+    pub enum PathMode {
+        /// one square per module (`M4,4h1v1h-1z`), as in the original output
+        Modules,
+        /// one rectangle per horizontal run of dark modules
+        Runs,
+        /// one outline per contiguous region of dark modules, the smallest output
+        Outlines,
+    }
+
+    impl FromStr for PathMode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "modules" => Ok(PathMode::Modules),
+                "runs" => Ok(PathMode::Runs),
+                "outlines" => Ok(PathMode::Outlines),
+                _ => Err(format!(
+                    "Unknown svg path mode: {}. Try one of: modules, runs, outlines",
+                    s
+                )),
+            }
+        }
+    }
+}
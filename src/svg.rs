@@ -0,0 +1,625 @@
+/// Configurable SVG output: sizes, path optimization, module and finder pattern shapes,
+/// gradients and per-region colors
+///
+/// Styling only applies where a scanner can cope with it. Finder patterns keep their 1:1:3:1:1
+/// proportions whatever their shape, timing and alignment patterns are always drawn as plain
+/// squares, and `SvgStyle::check()` rejects color combinations without enough contrast.
+///
+/// Square modules are merged into as few path commands as possible (see `PathMode`), which keeps
+/// documents for large codes small.
+pub mod methods {
+    use std::error;
+    use std::io::Write;
+
+    use crate::code::Credentials;
+    use crate::matrix::methods::QrMatrix;
+    use crate::render::methods::{Color, Length};
+    use crate::style::methods::{FinderShape, Gradient, ModuleShape, PathMode, SvgStyle};
+
+    /// the minimum contrast ratio (as defined by WCAG) between every dark color and the light color.
+    /// Printed codes with less contrast than this fail on a noticeable share of phone cameras.
+    pub const MIN_CONTRAST_RATIO: f64 = 3.0;
+
+    /// the radius of a dot, relative to the module size
+    const DOT_RADIUS: f64 = 0.45;
+    /// the corner radius of a rounded module, relative to the module size
+    const ROUNDED_MODULE_RADIUS: f64 = 0.3;
+
+    /// everything that controls how an SVG document is written
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SvgOptions {
+        /// size of the border (quiet zone) around the code, in modules
+        pub border: i32,
+        /// the physical width of the document. Defaults to `height`, or to the viewer's choice
+        pub width: Option<Length>,
+        /// the physical height of the document. Defaults to `width`, or to the viewer's choice
+        pub height: Option<Length>,
+        /// ask viewers not to anti-alias the edges of square modules, which avoids hairline
+        /// gaps between neighbouring modules
+        pub crisp_edges: bool,
+        /// the color of dark modules (and the start of the gradient, if any)
+        pub dark: Color,
+        /// the color of light modules and the border
+        pub light: Color,
+        /// shapes, gradient and finder colors
+        pub style: SvgStyle,
+        /// how square modules are written into the path data
+        pub paths: PathMode,
+        /// a short text alternative, written as `<title>` and `aria-label`, e.g. from
+        /// Credentials::accessible_name()
+        pub title: Option<String>,
+        /// a longer description, written as `<desc>`, e.g. from Credentials::accessible_description()
+        pub description: Option<String>,
+    }
+
+    impl Default for SvgOptions {
+        fn default() -> Self {
+            SvgOptions {
+                border: 4,
+                width: None,
+                height: None,
+                crisp_edges: true,
+                dark: Color::BLACK,
+                light: Color::WHITE,
+                style: SvgStyle::default(),
+                paths: PathMode::Outlines,
+                title: None,
+                description: None,
+            }
+        }
+    }
+
+    impl SvgOptions {
+        /// labels the SVG with the name and security of a network, so that screen readers can
+        /// announce it. The password is only included when `include_password` is set.
+        pub fn with_network(self, config: &Credentials, include_password: bool) -> SvgOptions {
+            SvgOptions {
+                title: Some(config.accessible_name()),
+                description: Some(config.accessible_description(include_password)),
+                ..self
+            }
+        }
+    }
+
+    impl SvgStyle {
+        /// every color used to draw dark modules with this style
+        fn dark_colors(&self, dark: Color) -> Vec<Color> {
+            let mut colors = vec![dark];
+            match self.gradient {
+                Some(Gradient::Linear { to }) | Some(Gradient::Radial { to }) => colors.push(to),
+                None => {}
+            }
+            colors.extend(self.finder_color);
+            colors.extend(self.finder_center_color);
+            colors
+        }
+
+        /// checks that a code drawn with this style, and these colors, stays scannable: every dark
+        /// color (gradient stops and finder colors included) must be darker than the light color, with
+        /// a contrast ratio of at least MIN_CONTRAST_RATIO
+        pub fn check(&self, dark: Color, light: Color) -> Result<(), StyleError> {
+            for color in self.dark_colors(dark) {
+                let ratio = contrast_ratio(color, light);
+
+                if luminance(color) >= luminance(light) {
+                    return Err(StyleError(format!(
+                        "The dark color {} is lighter than the light color {}. Many scanners cannot read inverted codes.",
+                        color.to_hex(),
+                        light.to_hex()
+                    )));
+                }
+
+                if ratio < MIN_CONTRAST_RATIO {
+                    return Err(StyleError(format!(
+                        "The contrast between {} and {} is {:.1}:1, below the minimum of {:.1}:1. Try a darker dark color or a lighter light color.",
+                        color.to_hex(),
+                        light.to_hex(),
+                        ratio,
+                        MIN_CONTRAST_RATIO
+                    )));
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// WCAG relative luminance of a color
+    fn luminance(color: Color) -> f64 {
+        let channel = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+    }
+
+    /// WCAG contrast ratio between two colors, from 1 (no contrast) to 21 (black on white)
+    pub fn contrast_ratio(a: Color, b: Color) -> f64 {
+        let (la, lb) = (luminance(a), luminance(b));
+        (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+    }
+
+    /// returns the centers of the alignment patterns for a QR code of this size, as
+    /// in section 6.3.6 of ISO/IEC 18004 (and qrcodegen's get_alignment_pattern_positions)
+    fn alignment_positions(size: i32) -> Vec<i32> {
+        let version = (size - 17) / 4;
+        if version == 1 {
+            return Vec::new();
+        }
+
+        let count = version / 7 + 2;
+        let step = if version == 32 {
+            26
+        } else {
+            (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+        };
+
+        let mut positions: Vec<i32> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+        positions.push(6);
+        positions.reverse();
+        positions
+    }
+
+    /// the top-left corners of the three finder patterns
+    fn finder_origins(size: i32) -> [(i32, i32); 3] {
+        [(0, 0), (size - 7, 0), (0, size - 7)]
+    }
+
+    /// whether a module belongs to one of the finder patterns
+    fn is_finder(size: i32, x: i32, y: i32) -> bool {
+        finder_origins(size)
+            .iter()
+            .any(|&(fx, fy)| (fx..fx + 7).contains(&x) && (fy..fy + 7).contains(&y))
+    }
+
+    /// whether a module belongs to a timing or alignment pattern, which are always drawn square
+    fn is_function_pattern(size: i32, alignment: &[i32], x: i32, y: i32) -> bool {
+        if is_finder(size, x, y) {
+            return false;
+        }
+
+        if x == 6 || y == 6 {
+            return true;
+        }
+
+        // alignment patterns that would overlap a finder pattern are left out of the symbol
+        alignment.iter().any(|&ax| {
+            alignment
+                .iter()
+                .any(|&ay| (ax - x).abs() <= 2 && (ay - y).abs() <= 2 && !is_finder(size, ax, ay))
+        })
+    }
+
+    /// path data for a rectangle with rounded corners
+    fn rounded_rect(x: f64, y: f64, w: f64, h: f64, r: f64) -> String {
+        format!(
+            "M{},{}h{}a{r},{r} 0 0 1 {r},{r}v{}a{r},{r} 0 0 1 -{r},{r}h-{}a{r},{r} 0 0 1 -{r},-{r}v-{}a{r},{r} 0 0 1 {r},-{r}z",
+            x + r,
+            y,
+            w - 2.0 * r,
+            h - 2.0 * r,
+            w - 2.0 * r,
+            h - 2.0 * r,
+            r = r
+        )
+    }
+
+    /// path data for a circle
+    fn circle(cx: f64, cy: f64, r: f64) -> String {
+        format!(
+            "M{},{}a{r},{r} 0 1 0 {d},0a{r},{r} 0 1 0 -{d},0z",
+            cx - r,
+            cy,
+            r = r,
+            d = 2.0 * r
+        )
+    }
+
+    /// path data for a single dark data module
+    fn module_path(shape: ModuleShape, x: f64, y: f64) -> String {
+        match shape {
+            ModuleShape::Square => format!("M{},{}h1v1h-1z", x, y),
+            ModuleShape::Rounded => rounded_rect(x, y, 1.0, 1.0, ROUNDED_MODULE_RADIUS),
+            ModuleShape::Dot => circle(x + 0.5, y + 0.5, DOT_RADIUS),
+        }
+    }
+
+    /// path data for a finder pattern's 7x7 outer ring (drawn with fill-rule="evenodd")
+    /// and its 3x3 center
+    fn finder_paths(shape: FinderShape, x: f64, y: f64) -> (String, String) {
+        match shape {
+            FinderShape::Square => (
+                format!("M{},{}h7v7h-7z M{},{}h5v5h-5z", x, y, x + 1.0, y + 1.0),
+                format!("M{},{}h3v3h-3z", x + 2.0, y + 2.0),
+            ),
+            FinderShape::Rounded => (
+                format!(
+                    "{} {}",
+                    rounded_rect(x, y, 7.0, 7.0, 2.0),
+                    rounded_rect(x + 1.0, y + 1.0, 5.0, 5.0, 1.5)
+                ),
+                rounded_rect(x + 2.0, y + 2.0, 3.0, 3.0, 1.0),
+            ),
+            FinderShape::Circle => (
+                format!(
+                    "{} {}",
+                    circle(x + 3.5, y + 3.5, 3.5),
+                    circle(x + 3.5, y + 3.5, 2.5)
+                ),
+                circle(x + 3.5, y + 3.5, 1.5),
+            ),
+        }
+    }
+
+    /// escapes text for use in XML (and HTML) content and attribute values
+    pub(crate) fn escape_xml(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => escaped += "&amp;",
+                '<' => escaped += "&lt;",
+                '>' => escaped += "&gt;",
+                '"' => escaped += "&quot;",
+                '\'' => escaped += "&apos;",
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// path data with one square per dark module
+    fn modules_path(size: i32, border: i32, dark: &dyn Fn(i32, i32) -> bool) -> String {
+        let mut data = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                if dark(x, y) {
+                    data.push(format!("M{},{}h1v1h-1z", x + border, y + border));
+                }
+            }
+        }
+        data.join(" ")
+    }
+
+    /// path data with one rectangle per horizontal run of dark modules
+    fn runs_path(size: i32, border: i32, dark: &dyn Fn(i32, i32) -> bool) -> String {
+        let mut data = Vec::new();
+        for y in 0..size {
+            let mut x = 0;
+            while x < size {
+                if !dark(x, y) {
+                    x += 1;
+                    continue;
+                }
+
+                let start = x;
+                while x < size && dark(x, y) {
+                    x += 1;
+                }
+                data.push(format!(
+                    "M{},{}h{}v1h-{}z",
+                    start + border,
+                    y + border,
+                    x - start,
+                    x - start
+                ));
+            }
+        }
+        data.join(" ")
+    }
+
+    /// unit steps along the module grid, in clockwise order: right, down, left, up
+    const STEPS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+    /// path data with one outline per contiguous region of dark modules
+    ///
+    /// Every edge between a dark and a light module becomes a directed edge between two grid
+    /// corners, oriented so that the dark module is on its right. Outer edges of a region then run
+    /// clockwise and the edges of holes counter-clockwise, so chaining edges into closed loops
+    /// draws the regions, holes included, with the default nonzero fill rule.
+    fn outlines_path(size: i32, border: i32, dark: &dyn Fn(i32, i32) -> bool) -> String {
+        let corners = (size + 1) as usize;
+        let index = |x: i32, y: i32| y as usize * corners + x as usize;
+        let is_dark =
+            |x: i32, y: i32| (0..size).contains(&x) && (0..size).contains(&y) && dark(x, y);
+
+        // a bit per step leaving each grid corner
+        let mut edges = vec![0u8; corners * corners];
+        for y in 0..size {
+            for x in 0..size {
+                if !is_dark(x, y) {
+                    continue;
+                }
+                if !is_dark(x, y - 1) {
+                    edges[index(x, y)] |= 1 << 0;
+                }
+                if !is_dark(x + 1, y) {
+                    edges[index(x + 1, y)] |= 1 << 1;
+                }
+                if !is_dark(x, y + 1) {
+                    edges[index(x + 1, y + 1)] |= 1 << 2;
+                }
+                if !is_dark(x - 1, y) {
+                    edges[index(x, y + 1)] |= 1 << 3;
+                }
+            }
+        }
+
+        let mut data = String::new();
+        for start_y in 0..=size {
+            for start_x in 0..=size {
+                while edges[index(start_x, start_y)] != 0 {
+                    let (mut x, mut y) = (start_x, start_y);
+                    let mut step = edges[index(x, y)].trailing_zeros() as usize;
+                    // (step, length) of each straight side of the loop
+                    let mut sides: Vec<(usize, i32)> = Vec::new();
+
+                    loop {
+                        edges[index(x, y)] &= !(1 << step);
+                        x += STEPS[step].0;
+                        y += STEPS[step].1;
+
+                        match sides.last_mut() {
+                            Some((s, length)) if *s == step => *length += 1,
+                            _ => sides.push((step, 1)),
+                        }
+
+                        if (x, y) == (start_x, start_y) {
+                            break;
+                        }
+
+                        // where two regions touch diagonally, turn right to keep them apart
+                        let available = edges[index(x, y)];
+                        step = [(step + 1) % 4, step, (step + 3) % 4]
+                            .iter()
+                            .copied()
+                            .find(|s| available & (1 << s) != 0)
+                            .expect("every corner of an outline has an outgoing edge");
+                    }
+
+                    data += &format!("M{},{}", start_x + border, start_y + border);
+                    // the closing side is drawn by `z`
+                    for &(step, length) in &sides[..sides.len() - 1] {
+                        let (dx, dy) = STEPS[step];
+                        if dy == 0 {
+                            data += &format!("h{}", dx * length);
+                        } else {
+                            data += &format!("v{}", dy * length);
+                        }
+                    }
+                    data.push('z');
+                }
+            }
+        }
+        data
+    }
+
+    /// writes an SVG
+    ///
+    /// * qr: &QrMatrix
+    ///
+    /// * options: border, size, colors, style and path optimization. Callers should check the
+    ///   style, and its colors, with SvgStyle::check() first.
+    ///
+    /// * out: where to write the SVG document
+    pub fn write_svg_with_options(
+        qr: &QrMatrix,
+        options: &SvgOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), Box<dyn error::Error>> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        write_svg_element(qr, options, out)
+    }
+
+    /// writes the `<svg>` element alone, without an XML declaration, e.g. for inlining into HTML
+    pub(crate) fn write_svg_element(
+        qr: &QrMatrix,
+        options: &SvgOptions,
+        out: &mut dyn Write,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let (border, dark, light, style) =
+            (options.border, options.dark, options.light, options.style);
+
+        if border < 0 {
+            return Err(StyleError("Border must be non-negative".to_string()).into());
+        }
+
+        let size = qr.size();
+        let dimension = size + border * 2;
+        let alignment = alignment_positions(size);
+        let b = f64::from(border);
+
+        // finder patterns are drawn as single shapes, unless they look exactly like plain modules
+        let separate_finders = style.finder_shape != FinderShape::Square
+            || style.finder_color.is_some()
+            || style.finder_center_color.is_some()
+            || style.module_shape != ModuleShape::Square;
+
+        // a code is square, so a single given dimension applies to both
+        let physical_size = match (
+            options.width.or(options.height),
+            options.height.or(options.width),
+        ) {
+            (Some(width), Some(height)) => format!(" width=\"{}\" height=\"{}\"", width, height),
+            _ => String::new(),
+        };
+
+        // labelled images are announced as a single image; unlabelled ones are left as they were
+        let label = match &options.title {
+            Some(title) => format!(" role=\"img\" aria-label=\"{}\"", escape_xml(title)),
+            None => String::new(),
+        };
+
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\"{1}{2} viewBox=\"0 0 {0} {0}\" stroke=\"none\">",
+            dimension, physical_size, label
+        )?;
+
+        // <title> and <desc> must be the first children to be picked up by assistive technology
+        if let Some(title) = &options.title {
+            writeln!(out, "\t<title>{}</title>", escape_xml(title))?;
+        }
+        if let Some(description) = &options.description {
+            writeln!(out, "\t<desc>{}</desc>", escape_xml(description))?;
+        }
+
+        let fill = match style.gradient {
+            Some(gradient) => {
+                let (start, end) = (f64::from(border), f64::from(border + size));
+                writeln!(out, "\t<defs>")?;
+                match gradient {
+                    Gradient::Linear { to } => writeln!(
+                        out,
+                        "\t\t<linearGradient id=\"wifiqr-fill\" gradientUnits=\"userSpaceOnUse\" x1=\"{0}\" y1=\"{0}\" x2=\"{1}\" y2=\"{1}\"><stop offset=\"0\" stop-color=\"{2}\"/><stop offset=\"1\" stop-color=\"{3}\"/></linearGradient>",
+                        start,
+                        end,
+                        dark.to_hex(),
+                        to.to_hex()
+                    )?,
+                    Gradient::Radial { to } => writeln!(
+                        out,
+                        "\t\t<radialGradient id=\"wifiqr-fill\" gradientUnits=\"userSpaceOnUse\" cx=\"{0}\" cy=\"{0}\" r=\"{1:.2}\"><stop offset=\"0\" stop-color=\"{2}\"/><stop offset=\"1\" stop-color=\"{3}\"/></radialGradient>",
+                        f64::from(dimension) / 2.0,
+                        f64::from(size) / std::f64::consts::SQRT_2,
+                        dark.to_hex(),
+                        to.to_hex()
+                    )?,
+                }
+                writeln!(out, "\t</defs>")?;
+                "url(#wifiqr-fill)".to_string()
+            }
+            None => dark.to_hex(),
+        };
+
+        writeln!(
+            out,
+            "\t<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            light.to_hex()
+        )?;
+
+        // modules drawn with the data module shape, as opposed to plain squares
+        let shaped = |x: i32, y: i32| {
+            style.module_shape != ModuleShape::Square
+                && !is_finder(size, x, y)
+                && !is_function_pattern(size, &alignment, x, y)
+        };
+        let square = |x: i32, y: i32| {
+            qr.get_module(x, y) && !(separate_finders && is_finder(size, x, y)) && !shaped(x, y)
+        };
+
+        let squares = match options.paths {
+            PathMode::Modules => modules_path(size, border, &square),
+            PathMode::Runs => runs_path(size, border, &square),
+            PathMode::Outlines => outlines_path(size, border, &square),
+        };
+        if !squares.is_empty() {
+            let crisp = if options.crisp_edges {
+                " shape-rendering=\"crispEdges\""
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "\t<path d=\"{}\"{} fill=\"{}\"/>",
+                squares, crisp, fill
+            )?;
+        }
+
+        let mut data = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                if qr.get_module(x, y) && shaped(x, y) {
+                    data.push(module_path(
+                        style.module_shape,
+                        f64::from(x) + b,
+                        f64::from(y) + b,
+                    ));
+                }
+            }
+        }
+        if !data.is_empty() {
+            writeln!(out, "\t<path d=\"{}\" fill=\"{}\"/>", data.join(" "), fill)?;
+        }
+
+        if separate_finders {
+            let finder_color = style.finder_color.unwrap_or(dark);
+            let center_color = style.finder_center_color.unwrap_or(finder_color);
+
+            let (rings, centers): (Vec<String>, Vec<String>) = finder_origins(size)
+                .iter()
+                .map(|&(x, y)| finder_paths(style.finder_shape, f64::from(x) + b, f64::from(y) + b))
+                .unzip();
+
+            writeln!(
+                out,
+                "\t<path d=\"{}\" fill-rule=\"evenodd\" fill=\"{}\"/>",
+                rings.join(" "),
+                finder_color.to_hex()
+            )?;
+            writeln!(
+                out,
+                "\t<path d=\"{}\" fill=\"{}\"/>",
+                centers.join(" "),
+                center_color.to_hex()
+            )?;
+        }
+
+        writeln!(out, "</svg>")?;
+        Ok(())
+    }
+
+    /// writes a styled SVG, with one path command per square module
+    ///
+    /// * qr: &QrMatrix
+    ///
+    /// * border: size of border to apply to the SVG, in modules
+    ///
+    /// * dark: the color of dark modules (and the start of the gradient, if any)
+    ///
+    /// * light: the color of light modules and the border
+    ///
+    /// * style: shapes, gradient and finder colors. Callers should check the style, and its colors,
+    ///   with SvgStyle::check() first.
+    ///
+    /// * out: where to write the SVG document
+    pub fn write_styled_svg(
+        qr: &QrMatrix,
+        border: i32,
+        dark: Color,
+        light: Color,
+        style: &SvgStyle,
+        out: &mut dyn Write,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let options = SvgOptions {
+            border,
+            crisp_edges: false,
+            dark,
+            light,
+            style: *style,
+            paths: PathMode::Modules,
+            ..SvgOptions::default()
+        };
+        write_svg_with_options(qr, &options, out)
+    }
+
+    /// this error is returned when an SVG style would produce a code that is hard or impossible to scan
+    #[derive(Debug, Clone)]
+    pub struct StyleError(String);
+
+    impl std::error::Error for StyleError {
+        fn description(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl std::fmt::Display for StyleError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+}
@@ -0,0 +1,197 @@
+/// Inline image output for terminals that support a graphics protocol
+///
+/// Block characters depend on the terminal font to line up; these protocols draw the
+/// rendered bitmap pixel-for-pixel instead, which scans far more reliably.
+pub mod methods {
+    use std::env;
+    use std::str::FromStr;
+
+    use image::{ImageBuffer, LumaA};
+
+    use crate::exporters::methods::write_png;
+
+    /// the largest base64 payload the kitty graphics protocol accepts in a single escape sequence
+    const KITTY_CHUNK_SIZE: usize = 4096;
+
+    /// the ways a QR code can be drawn on a terminal
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TerminalProtocol {
+        /// pick a protocol by looking at the environment. See TerminalProtocol::detect()
+        Auto,
+        /// DEC Sixel graphics (xterm -ti vt340, mlterm, foot, WezTerm, ...)
+        Sixel,
+        /// the kitty graphics protocol (kitty, WezTerm, Konsole, ...)
+        Kitty,
+        /// iTerm2's inline image escape (iTerm2, WezTerm, ...)
+        Iterm,
+        /// unicode block characters, which work everywhere
+        Blocks,
+    }
+
+    impl TerminalProtocol {
+        /// guesses the graphics protocol supported by the current terminal from well-known
+        /// environment variables. Falls back to TerminalProtocol::Blocks when nothing is recognized,
+        /// and never returns TerminalProtocol::Auto.
+        pub fn detect() -> TerminalProtocol {
+            let var = |name: &str| env::var(name).unwrap_or_default();
+
+            if var("TERM") == "xterm-kitty" || env::var_os("KITTY_WINDOW_ID").is_some() {
+                return TerminalProtocol::Kitty;
+            }
+
+            if var("TERM_PROGRAM") == "iTerm.app"
+                || var("LC_TERMINAL") == "iTerm2"
+                || var("TERM_PROGRAM") == "WezTerm"
+            {
+                return TerminalProtocol::Iterm;
+            }
+
+            let term = var("TERM");
+            if term.contains("sixel") || term.starts_with("mlterm") || term.starts_with("foot") {
+                return TerminalProtocol::Sixel;
+            }
+
+            TerminalProtocol::Blocks
+        }
+
+        /// resolves TerminalProtocol::Auto into a concrete protocol; other values are returned as-is
+        pub fn resolve(self) -> TerminalProtocol {
+            match self {
+                TerminalProtocol::Auto => TerminalProtocol::detect(),
+                p => p,
+            }
+        }
+    }
+
+    impl FromStr for TerminalProtocol {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "auto" => Ok(TerminalProtocol::Auto),
+                "sixel" => Ok(TerminalProtocol::Sixel),
+                "kitty" => Ok(TerminalProtocol::Kitty),
+                "iterm" | "iterm2" => Ok(TerminalProtocol::Iterm),
+                "blocks" => Ok(TerminalProtocol::Blocks),
+                _ => Err(format!(
+                    "Unknown console protocol: {}. Try one of: auto, sixel, kitty, iterm, blocks",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// a pixel is drawn dark when it is opaque and closer to black than white
+    fn is_dark(pixel: &LumaA<u8>) -> bool {
+        pixel[0] < 128 && pixel[1] >= 128
+    }
+
+    /// encodes an image as a PNG file in memory
+    fn to_png(image: &ImageBuffer<LumaA<u8>, Vec<u8>>) -> Result<Vec<u8>, image::ImageError> {
+        let mut png = Vec::new();
+        write_png(image, &mut png)?;
+        Ok(png)
+    }
+
+    /// appends a run of identical sixel characters, using the `!` repeat introducer for longer runs
+    fn push_sixel_run(out: &mut String, sixel: char, count: u32) {
+        match count {
+            0 => {}
+            1..=3 => (0..count).for_each(|_| out.push(sixel)),
+            _ => *out += &format!("!{}{}", count, sixel),
+        }
+    }
+
+    /// returns a DEC Sixel escape sequence that draws the image in two colors
+    ///
+    /// * image: a rendered QR code, as returned by make_image()
+    pub fn to_sixel_string(image: &ImageBuffer<LumaA<u8>, Vec<u8>>) -> String {
+        let (width, height) = image.dimensions();
+
+        let mut out = String::new();
+        // P1=0 (default aspect), P2=1 (pixels not drawn keep the background), then 1:1 raster attributes
+        out += &format!("\x1bP0;1q\"1;1;{};{}", width, height);
+        // color registers: 0 is white, 1 is black (RGB percentages)
+        out += "#0;2;100;100;100#1;2;0;0;0";
+
+        for band in (0..height).step_by(6) {
+            for (register, dark) in [(0, false), (1, true)].iter() {
+                out += &format!("#{}", register);
+
+                let mut run_sixel = '?';
+                let mut run_length = 0;
+
+                for x in 0..width {
+                    let mut bits = 0u8;
+                    for row in 0..6 {
+                        let y = band + row;
+                        if y < height && is_dark(image.get_pixel(x, y)) == *dark {
+                            bits |= 1 << row;
+                        }
+                    }
+
+                    let sixel = (b'?' + bits) as char;
+                    if sixel == run_sixel {
+                        run_length += 1;
+                    } else {
+                        push_sixel_run(&mut out, run_sixel, run_length);
+                        run_sixel = sixel;
+                        run_length = 1;
+                    }
+                }
+
+                push_sixel_run(&mut out, run_sixel, run_length);
+                // graphics carriage return: the second color is painted over the same band
+                out.push('$');
+            }
+            // graphics new line: move down to the next band of six rows
+            out.push('-');
+        }
+
+        out += "\x1b\\";
+        out
+    }
+
+    /// returns a kitty graphics protocol escape sequence that transmits and displays the image
+    ///
+    /// * image: a rendered QR code, as returned by make_image()
+    pub fn to_kitty_string(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+    ) -> Result<String, image::ImageError> {
+        let payload = base64::encode(to_png(image)?);
+        let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+
+        let mut out = String::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = if i + 1 < chunks.len() { 1 } else { 0 };
+
+            if i == 0 {
+                // f=100: PNG payload, a=T: transmit and display
+                out += &format!("\x1b_Gf=100,a=T,m={};", more);
+            } else {
+                out += &format!("\x1b_Gm={};", more);
+            }
+
+            // base64 output is always ASCII
+            out += std::str::from_utf8(chunk).unwrap();
+            out += "\x1b\\";
+        }
+
+        Ok(out)
+    }
+
+    /// returns an iTerm2 inline image escape sequence that displays the image
+    ///
+    /// * image: a rendered QR code, as returned by make_image()
+    pub fn to_iterm_string(
+        image: &ImageBuffer<LumaA<u8>, Vec<u8>>,
+    ) -> Result<String, image::ImageError> {
+        let png = to_png(image)?;
+
+        Ok(format!(
+            "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:{}\x07",
+            png.len(),
+            base64::encode(&png)
+        ))
+    }
+}
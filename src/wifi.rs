@@ -0,0 +1,511 @@
+/// The Wi-Fi network payload: credentials, escaping and validation
+///
+/// This is everything needed to build the `WIFI:` string that goes into a QR code. It only uses
+/// `core` and `alloc`, so firmware can build it without the standard library (see the `std`
+/// feature).
+pub mod methods {
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    #[cfg(feature = "serde")]
+    use alloc::borrow::Cow;
+    #[cfg(feature = "serde")]
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::confusable::methods::{find_suspicious, normalize_text};
+    use crate::profile::methods::{FieldOrder, Profile};
+
+    /// the version of the serialized Credentials schema, written as its `version` field. Fields
+    /// may be added within a version; renaming, removing or changing the meaning of one starts a
+    /// new version
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    /// the longest SSID an access point can broadcast, in octets (IEEE 802.11)
+    const MAX_SSID_LEN: usize = 32;
+
+    /// encodes bytes as an uppercase hex string, as in `WiFi.HexSSID` and hex SSIDs in payloads
+    pub(crate) fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// whether a value could be read as hex by readers that decode unquoted hex SSIDs and keys
+    fn looks_like_hex(value: &str) -> bool {
+        !value.is_empty() && value.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// whether a password is a raw key rather than a passphrase: 10, 26 or 58 hex digits for WEP
+    /// (40, 104 and 232 bit keys), or 64 for WPA (the pre-shared key itself). Access points and
+    /// readers both read these as hex, so they must not be quoted
+    pub(crate) fn is_hex_key(encryption: &str, password: &str) -> bool {
+        let lengths: &[usize] = match encryption.to_uppercase().as_str() {
+            "WEP" => &[10, 26, 58],
+            "WPA" | "WPA2" | "WPA3" => &[64],
+            _ => &[],
+        };
+        looks_like_hex(password) && lengths.contains(&password.len())
+    }
+
+    /// decodes a hex string (either case) into bytes, as for hex SSIDs. None when the length is
+    /// odd or a character isn't a hex digit
+    #[cfg(any(feature = "std", feature = "serde"))]
+    pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+        let pairs = hex.as_bytes().chunks_exact(2);
+        // from_str_radix() also takes a leading `+`, so every character is checked first
+        if !pairs.remainder().is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        pairs
+            .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    }
+
+    /// a Wi-Fi network. With the `serde` feature, it is serialized as a versioned schema that does
+    /// not depend on the field names here:
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "ssid": "Guest",
+    ///   "password": "correct horse",
+    ///   "encryption": "wpa2",
+    ///   "hidden": false,
+    ///   "quote": false
+    /// }
+    /// ```
+    ///
+    /// * version: required, and must be SCHEMA_VERSION
+    /// * ssid, encryption: required strings. Encryption is as passed to auth(), e.g. `wpa2` or `nopass`
+    /// * password: optional, empty when missing. See Credentials::redacted() to leave it out
+    /// * hidden, quote: optional, false when missing
+    /// * ssid_hex: optional, only written for SSIDs that are not valid UTF-8 (see raw_ssid)
+    /// * utf8_ssid: optional, false when missing, and only written when set
+    /// * profile: optional, `max-compat` when missing, and only written when different
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Credentials {
+        pub ssid: String,
+        pub pass: String,
+        pub encr: String,
+        pub hidden: bool,
+        pub quote: bool,
+        /// the SSID as octets, for network names that are not valid UTF-8. When set, the payload
+        /// carries it as unquoted hex, which Android and ZXing-based readers decode, and `ssid` is
+        /// only a lossy copy for people to read. See Credentials::with_ssid_bytes()
+        pub raw_ssid: Option<Vec<u8>>,
+        /// a hint that the SSID is UTF-8 text, as access points announce with the UTF-8 SSID
+        /// capability (hostapd's `utf8_ssid=1`). code::encode() then marks payloads that are not
+        /// plain ASCII with an ECI 26 (UTF-8) designator, so readers don't guess another charset,
+        /// e.g. Shift JIS for a Japanese network name
+        pub utf8_ssid: bool,
+        /// the reader the payload is formatted for: casing, quoting, escaping and field order.
+        /// See Profile
+        pub profile: Profile,
+    }
+
+    impl Credentials {
+        pub fn new(
+            mut _ssid: Option<&str>,
+            mut _password: Option<&str>,
+            mut _encr: Option<&str>,
+            mut _hidden: bool,
+            mut _quote: bool,
+        ) -> Self {
+            Credentials {
+                ssid: _ssid.unwrap().to_string(),
+                encr: _encr.unwrap().to_string(),
+                pass: _password.unwrap().to_string(),
+                hidden: _hidden,
+                quote: _quote,
+                raw_ssid: None,
+                utf8_ssid: false,
+                profile: Profile::default(),
+            }
+        }
+
+        /// sets the SSID from octets, as an access point broadcasts it. Valid UTF-8 becomes `ssid`
+        /// like any other name; anything else is kept in `raw_ssid`
+        pub fn with_ssid_bytes(mut self, ssid: &[u8]) -> Self {
+            match core::str::from_utf8(ssid) {
+                Ok(name) => {
+                    self.ssid = name.to_string();
+                    self.raw_ssid = None;
+                }
+                Err(_) => {
+                    self.ssid = String::from_utf8_lossy(ssid).into_owned();
+                    self.raw_ssid = Some(ssid.to_vec());
+                }
+            }
+            self
+        }
+
+        /// the SSID as octets: `raw_ssid` if set, otherwise the UTF-8 bytes of `ssid`
+        pub fn ssid_bytes(&self) -> &[u8] {
+            match &self.raw_ssid {
+                Some(bytes) => bytes,
+                None => self.ssid.as_bytes(),
+            }
+        }
+
+        /// the S: field of the payload: raw SSIDs in hex, others escaped
+        fn ssid_field(&self) -> String {
+            match &self.raw_ssid {
+                Some(bytes) => to_hex(bytes),
+                None => self.filter_credentials(&self.ssid),
+            }
+        }
+
+        /// escape characters as in:
+        /// https://github.com/zxing/zxing/wiki/Barcode-Contents#wifi-network-config-android
+        /// Special characters `\`, `;` and `"` are escaped with a backslash, and `,` and `:` too
+        /// when the profile asks for it. Readers find the end of a field at the next unescaped
+        /// `;`, so the separators are safe to leave as they are for readers that don't undo escapes
+        fn filter_credentials(&self, field: &str) -> String {
+            // N.B. If performance problems ever crop up, this might be more performant
+            // with regex replace_all
+
+            let mut filtered = field
+                .to_string()
+                .replace('\\', r#"\\"#)
+                .replace('"', r#"\""#)
+                .replace(';', r#"\;"#);
+
+            if self.profile.escapes_separators() {
+                filtered = filtered.replace(':', r#"\:"#).replace(',', r#"\,"#);
+            }
+
+            let hex =
+                looks_like_hex(field) && !(field == self.pass && is_hex_key(&self.encr, field));
+            let quote =
+                (self.quote && self.profile.allows_quotes()) || (self.profile.quotes_hex() && hex);
+
+            if (filtered == self.ssid || filtered == self.pass) && quote {
+                filtered = format!("\"{}\"", field);
+            }
+
+            filtered
+        }
+
+        /// the encryption field in the Wifi QR code fails on iOS devices if it is
+        /// not provided in an uppercase format. Android devices are case insensitive,
+        /// so the encryption field is passed through as uppercase, unless the profile says otherwise.
+        fn filter_encr(&self, field: &str) -> Result<String, FormatError> {
+            if self.profile.strict_encryption() {
+                return match field.to_uppercase().as_str() {
+                    "WEP" => Ok("WEP".to_string()),
+                    "WPA" | "WPA2" | "WPA3" => Ok("WPA".to_string()),
+                    _ => Err(FormatError(format!(
+                        "The strict profile only allows the encryption types WEP, WPA and nopass, not {}. Enterprise (EAP) networks need settings a WIFI: payload can't carry.",
+                        field
+                    ))),
+                };
+            }
+
+            if self.profile.uppercase_encryption() {
+                Ok(field.to_uppercase())
+            } else {
+                Ok(field.to_string())
+            }
+        }
+
+        /// Build the qr-string, laid out as self.profile asks, and/or return any errors that
+        /// need to be raised to the caller. Note: format does not enforce an encryption type, it is
+        /// up to the end user to use the right value if one is provided.
+        pub fn format(&self) -> Result<String, FormatError> {
+            // empty password ->
+            //  * is password empty and ssid hidden? => set T:nopass and H:
+            //  * is encryption type empty? => set nopass
+            //  * hidden ssid? => add H:
+            // plain format
+            // unrecoverable errors:
+            // * ssid has no password, but sets a T type
+            // * sets a password, but sets T type to nopass
+            // * the SSID is longer than 32 octets
+            if self.ssid_bytes().len() > MAX_SSID_LEN {
+                return Err(FormatError(format!(
+                    "The SSID is {} bytes long, but an SSID can be at most {} bytes.",
+                    self.ssid_bytes().len(),
+                    MAX_SSID_LEN
+                )));
+            }
+
+            let nopass = self.encr.is_empty() || self.encr == "nopass";
+
+            if nopass && !self.pass.is_empty() {
+                return Err(FormatError(
+                    "With nopass as the encryption type (or unset encryption type), 
+                    the password field should be empty. (Encryption should probably be set 
+                    to something like wpa2)"
+                        .to_string(),
+                ));
+            }
+
+            // Error condition: Password is empty, and the T (encr) type is not "nopass" / not empty
+            if self.pass.is_empty() && !nopass {
+                return Err(FormatError(
+                    "The encryption method requested requires a password.".to_string(),
+                ));
+            }
+
+            // Derived from:
+            // https://github.com/zxing/zxing/wiki/Barcode-Contents#wifi-network-config-android
+            //
+            // T: authentication type (WEP, WPA, 'nopass'). Can be ommitted for no password.
+            // S: network SSID
+            // P: wifi password. Can be ommitted if T is 'nopass'
+            // H: Hidden SSID. Optional.
+            let encryption = if nopass {
+                "nopass".to_string()
+            } else {
+                self.filter_credentials(&self.filter_encr(&self.encr)?)
+            };
+
+            let mut fields = match self.profile.field_order() {
+                FieldOrder::EncryptionFirst => vec![("T", encryption), ("S", self.ssid_field())],
+                FieldOrder::SsidFirst => vec![("S", self.ssid_field()), ("T", encryption)],
+            };
+            if !nopass {
+                fields.push(("P", self.filter_credentials(&self.pass)));
+            }
+            if self.hidden || self.profile.explicit_hidden() {
+                fields.push(("H", self.hidden.to_string()));
+            }
+
+            let mut payload = "WIFI:".to_string();
+            for (key, value) in fields {
+                payload += &format!("{}:{};", key, value);
+            }
+            payload.push(';');
+
+            Ok(payload)
+        }
+
+        /// characters in the SSID or password that were likely pasted in by accident: invisible
+        /// characters, unusual spaces, accents stored apart from their letter, and Cyrillic or
+        /// Greek look-alikes among Latin letters. The payload carries them as they are, so the code
+        /// names a different network or password than the one typed into the access point. Each
+        /// warning gives the code point (and, for the SSID, the position); see
+        /// Credentials::normalized() to fix them
+        pub fn warnings(&self) -> Vec<String> {
+            let mut warnings = Vec::new();
+
+            let fields = [
+                (
+                    "SSID",
+                    if self.raw_ssid.is_none() {
+                        self.ssid.as_str()
+                    } else {
+                        ""
+                    },
+                ),
+                ("password", self.pass.as_str()),
+            ];
+            for (name, value) in fields.iter() {
+                for suspect in find_suspicious(value) {
+                    let fix = if suspect.is_normalized() {
+                        ". Normalizing fixes this (Credentials::normalized(), or --normalize)"
+                    } else {
+                        ""
+                    };
+                    // the password is described without its position or the letter it looks like
+                    let suspect = if *name == "password" {
+                        suspect.redacted()
+                    } else {
+                        suspect.to_string()
+                    };
+                    warnings.push(format!("The {} contains {}{}", name, suspect, fix));
+                }
+            }
+
+            warnings
+        }
+
+        /// returns the credentials with the SSID and password normalized: invisible characters
+        /// removed, unusual spaces made plain, detached accents composed, and look-alike letters
+        /// replaced with Latin ones (see Credentials::warnings()). Raw SSIDs are kept as they are.
+        /// This is opt-in, as a network may really be named with any of these characters
+        pub fn normalized(mut self) -> Self {
+            if self.raw_ssid.is_none() {
+                self.ssid = normalize_text(&self.ssid);
+            }
+            self.pass = normalize_text(&self.pass);
+            self
+        }
+
+        /// a short text alternative for a QR code of this network, e.g. "Wi-Fi network Guest, WPA2".
+        /// Never includes the password.
+        pub fn accessible_name(&self) -> String {
+            let security = match self.encr.to_lowercase().as_str() {
+                "" | "nopass" => "no password".to_string(),
+                _ => self.encr.to_uppercase(),
+            };
+
+            if self.hidden {
+                format!("Wi-Fi network {}, {}, hidden", self.ssid, security)
+            } else {
+                format!("Wi-Fi network {}, {}", self.ssid, security)
+            }
+        }
+
+        /// a longer description of a QR code of this network, telling readers what scanning it does
+        ///
+        /// * include_password: also spell out the password, for pages that are only shown to guests
+        pub fn accessible_description(&self, include_password: bool) -> String {
+            let mut description = format!(
+                "QR code that joins the Wi-Fi network {} when scanned with a phone camera.",
+                self.ssid
+            );
+
+            if include_password && !self.pass.is_empty() {
+                description += &format!(" The password is {}.", self.pass);
+            }
+
+            description
+        }
+    }
+
+    /// the serialized form of Credentials, at SCHEMA_VERSION
+    #[cfg(feature = "serde")]
+    #[derive(Serialize, Deserialize)]
+    struct CredentialsSchema<'a> {
+        version: u32,
+        ssid: Cow<'a, str>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<Cow<'a, str>>,
+        encryption: Cow<'a, str>,
+        #[serde(default)]
+        hidden: bool,
+        #[serde(default)]
+        quote: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ssid_hex: Option<String>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        utf8_ssid: bool,
+        #[serde(default, skip_serializing_if = "is_max_compat")]
+        profile: Profile,
+    }
+
+    #[cfg(feature = "serde")]
+    fn is_false(value: &bool) -> bool {
+        !*value
+    }
+
+    #[cfg(feature = "serde")]
+    fn is_max_compat(profile: &Profile) -> bool {
+        *profile == Profile::MaxCompat
+    }
+
+    #[cfg(feature = "serde")]
+    impl Credentials {
+        fn schema(&self, include_password: bool) -> CredentialsSchema<'_> {
+            CredentialsSchema {
+                version: SCHEMA_VERSION,
+                ssid: Cow::Borrowed(&self.ssid),
+                password: if include_password {
+                    Some(Cow::Borrowed(&self.pass))
+                } else {
+                    None
+                },
+                encryption: Cow::Borrowed(&self.encr),
+                hidden: self.hidden,
+                quote: self.quote,
+                ssid_hex: self.raw_ssid.as_deref().map(to_hex),
+                utf8_ssid: self.utf8_ssid,
+                profile: self.profile,
+            }
+        }
+
+        /// a view of these credentials that serializes without the password, e.g. for logs
+        pub fn redacted(&self) -> RedactedCredentials<'_> {
+            RedactedCredentials(self)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl Serialize for Credentials {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.schema(true).serialize(serializer)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> Deserialize<'de> for Credentials {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let schema = CredentialsSchema::deserialize(deserializer)?;
+            if schema.version != SCHEMA_VERSION {
+                return Err(de::Error::custom(format!(
+                    "unsupported Credentials schema version {} (expected {})",
+                    schema.version, SCHEMA_VERSION
+                )));
+            }
+
+            let config = Credentials {
+                ssid: schema.ssid.into_owned(),
+                pass: schema.password.map(Cow::into_owned).unwrap_or_default(),
+                encr: schema.encryption.into_owned(),
+                hidden: schema.hidden,
+                quote: schema.quote,
+                raw_ssid: None,
+                utf8_ssid: schema.utf8_ssid,
+                profile: schema.profile,
+            };
+
+            match schema.ssid_hex {
+                Some(hex) => match from_hex(&hex) {
+                    Some(bytes) => Ok(config.with_ssid_bytes(&bytes)),
+                    None => Err(de::Error::custom(format!(
+                        "ssid_hex `{}` is not a valid hex string",
+                        hex
+                    ))),
+                },
+                None => Ok(config),
+            }
+        }
+    }
+
+    /// Credentials that serialize without their password, from Credentials::redacted()
+    #[cfg(feature = "serde")]
+    #[derive(Debug, Clone, Copy)]
+    pub struct RedactedCredentials<'a>(&'a Credentials);
+
+    #[cfg(feature = "serde")]
+    impl Serialize for RedactedCredentials<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.schema(false).serialize(serializer)
+        }
+    }
+
+    /// returns a new Credentials struct given Wifi credentials. This data is not validated,
+    /// nor formatted into a QR code string. Call .format() on Credentials to do this.
+    pub fn auth(
+        _ssid: Option<&str>,
+        _password: Option<&str>,
+        _encr: Option<&str>,
+        _hidden: bool,
+        _quote: bool,
+    ) -> Credentials {
+        self::Credentials::new(_ssid, _password, _encr, _hidden, _quote)
+    }
+
+    /// this error is returned when a potentally invalid combination of choices are made in the process
+    /// of building a wifi connection string to embed as a QR code.
+    ///
+    /// a recommendation is returned to the caller as a string to provide corrective action
+    #[derive(Debug, Clone)]
+    pub struct FormatError(String);
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for FormatError {
+        fn description(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl fmt::Display for FormatError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+}